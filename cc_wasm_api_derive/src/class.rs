@@ -0,0 +1,116 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{FnArg, ImplItem, ItemImpl, Pat, Type, Visibility};
+
+use crate::export::ExportOpts;
+
+/// implements `LuaClass` for the type of an `impl` block
+pub(crate) fn expand(opts: ExportOpts, item: ItemImpl) -> syn::Result<TokenStream2> {
+    if opts.args.is_some() {
+        return Err(syn::Error::new_spanned(
+            &item.self_ty,
+            "`args` is not supported on impl blocks",
+        ));
+    }
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "cc_export can not be used on trait impls",
+        ));
+    }
+    let class_name = match opts.name {
+        Some(name) => name,
+        None => match &*item.self_ty {
+            Type::Path(p) => p.path.segments.last().unwrap().ident.to_string(),
+            ty => {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "expect a type name, or set it with `name = \"...\"`",
+                ))
+            }
+        },
+    };
+
+    let mut names = Vec::new();
+    let mut arms = Vec::new();
+    for impl_item in &item.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        let sig = &method.sig;
+        let is_method = matches!(
+            sig.inputs.first(),
+            Some(FnArg::Receiver(r)) if r.reference.is_some()
+        );
+        if !is_method || !matches!(method.vis, Visibility::Public(_)) {
+            continue;
+        }
+        if sig.asyncness.is_some() {
+            return Err(syn::Error::new_spanned(
+                sig.asyncness,
+                "async methods can not be exported",
+            ));
+        }
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &sig.generics,
+                "exported methods can not be generic",
+            ));
+        }
+
+        let ident = &sig.ident;
+        let name = ident.to_string();
+        let mut arg_names = Vec::new();
+        let mut params = Vec::new();
+        let mut call_args = Vec::new();
+        for (i, input) in sig.inputs.iter().skip(1).enumerate() {
+            let FnArg::Typed(t) = input else {
+                unreachable!("only the first argument can be self")
+            };
+            if let Pat::Ident(p) = &*t.pat {
+                arg_names.push(p.ident.to_string());
+            }
+            let arg = format_ident!("__arg{}", i);
+            let ty = &t.ty;
+            params.push(quote!(#arg: #ty));
+            call_args.push(arg);
+        }
+        arms.push(quote! {
+            #name => {
+                const INFO: ::cc_wasm_api::cc_mod::ExportInfo =
+                    ::cc_wasm_api::cc_mod::ExportInfo::new(#name).with_args(&[#(#arg_names),*]);
+                // `ExportFunc` takes `Fn`, the object is reborrowed in the closure
+                let this = ::core::cell::RefCell::new(self);
+                unsafe {
+                    ::cc_wasm_api::cc_mod::ExportFunc::call(
+                        &|#(#params),*| Self::#ident(&mut **this.borrow_mut(), #(#call_args),*),
+                        &INFO,
+                    )
+                };
+            }
+        });
+        names.push(name);
+    }
+
+    let self_ty = &item.self_ty;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+    Ok(quote! {
+        #item
+
+        impl #impl_generics ::cc_wasm_api::cc_mod::object::LuaClass for #self_ty #where_clause {
+            fn class_name() -> &'static str {
+                #class_name
+            }
+            fn methods() -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+            unsafe fn call_method(&mut self, method: &str) -> bool {
+                match method {
+                    #(#arms)*
+                    _ => return false,
+                }
+                true
+            }
+        }
+    })
+}
@@ -0,0 +1,151 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    meta::ParseNestedMeta, spanned::Spanned, Expr, FnArg, Ident, ItemFn, LitStr, Pat, Token,
+};
+
+/// options of `#[cc_export(...)]`
+#[derive(Default)]
+pub(crate) struct ExportOpts {
+    pub(crate) name: Option<String>,
+    /// `None` uses the parameter names of the function
+    pub(crate) args: Option<Vec<ArgOpt>>,
+}
+pub(crate) struct ArgOpt {
+    name: Ident,
+    default: Option<Expr>,
+}
+impl ExportOpts {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            let name: LitStr = meta.value()?.parse()?;
+            self.name = Some(name.value());
+            Ok(())
+        } else if meta.path.is_ident("args") {
+            let args = self.args.get_or_insert_with(Vec::new);
+            meta.parse_nested_meta(|arg| {
+                let name = arg
+                    .path
+                    .get_ident()
+                    .cloned()
+                    .ok_or_else(|| arg.error("expect an argument name"))?;
+                let default = if arg.input.peek(Token![=]) {
+                    Some(arg.value()?.parse()?)
+                } else {
+                    None
+                };
+                args.push(ArgOpt { name, default });
+                Ok(())
+            })
+        } else {
+            Err(meta.error("unknown cc_export option, expect `name` or `args`"))
+        }
+    }
+}
+
+pub(crate) fn expand(opts: ExportOpts, func: ItemFn) -> syn::Result<TokenStream2> {
+    let sig = &func.sig;
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "exported functions can not be generic",
+        ));
+    }
+    let mut params = Vec::new();
+    for input in &sig.inputs {
+        match input {
+            FnArg::Receiver(r) => {
+                return Err(syn::Error::new_spanned(
+                    r,
+                    "exported functions can not take `self`",
+                ))
+            }
+            FnArg::Typed(t) => params.push(t),
+        }
+    }
+    let args = match opts.args {
+        Some(args) if args.len() > params.len() => {
+            return Err(syn::Error::new(
+                sig.inputs.span(),
+                "more `args` than function parameters",
+            ))
+        }
+        Some(args) => args,
+        None => params
+            .iter()
+            .filter_map(|p| match &*p.pat {
+                Pat::Ident(i) => Some(ArgOpt {
+                    name: i.ident.clone(),
+                    default: None,
+                }),
+                _ => None,
+            })
+            .collect(),
+    };
+
+    let func_name = &sig.ident;
+    let export_name = opts.name.unwrap_or_else(|| func_name.to_string());
+    let arg_names = args.iter().map(|a| a.name.to_string());
+
+    let call = if args.iter().all(|a| a.default.is_none()) {
+        quote!(&#func_name)
+    } else {
+        let mut closure_params = Vec::new();
+        let mut call_args = Vec::new();
+        for (i, param) in params.iter().enumerate() {
+            let ident = format_ident!("__arg{}", i);
+            let ty = &param.ty;
+            match args.get(i).and_then(|a| a.default.as_ref()) {
+                Some(default) => {
+                    closure_params.push(quote!(#ident: ::core::option::Option<#ty>));
+                    call_args.push(quote!(#ident.unwrap_or_else(|| #default)));
+                }
+                None => {
+                    closure_params.push(quote!(#ident: #ty));
+                    call_args.push(quote!(#ident));
+                }
+            }
+        }
+        quote!(&|#(#closure_params),*| #func_name(#(#call_args),*))
+    };
+
+    Ok(quote! {
+        #func
+
+        const _: () = {
+            const INFO: ::cc_wasm_api::cc_mod::ExportInfo =
+                ::cc_wasm_api::cc_mod::ExportInfo::new(#export_name)
+                    .with_args(&[#(#arg_names),*]);
+
+            #[unsafe(export_name = #export_name)]
+            extern "C" fn __cc_export() {
+                if ::cc_wasm_api::cc_mod::panic::report_poisoned() {
+                    return;
+                }
+                unsafe { ::cc_wasm_api::cc_mod::ExportFunc::call(#call, &INFO) };
+            }
+
+            fn __describe(info: &::cc_wasm_api::cc_mod::ExportInfo) -> ::std::string::String {
+                ::cc_wasm_api::cc_mod::ExportFunc::describe(#call, info)
+            }
+
+            static REGISTERED: ::cc_wasm_api::cc_mod::registry::Registered =
+                ::cc_wasm_api::cc_mod::registry::Registered::new(INFO, __describe);
+
+            // runs before any exported function, the same way as `.init_array` constructors in C
+            #[used]
+            #[cfg_attr(
+                any(target_os = "linux", target_os = "android", target_os = "freebsd", target_family = "wasm"),
+                unsafe(link_section = ".init_array")
+            )]
+            #[cfg_attr(any(target_os = "macos", target_os = "ios"), unsafe(link_section = "__DATA,__mod_init_func"))]
+            #[cfg_attr(windows, unsafe(link_section = ".CRT$XCU"))]
+            static REGISTER: extern "C" fn() = {
+                extern "C" fn register() {
+                    ::cc_wasm_api::cc_mod::registry::submit(&REGISTERED);
+                }
+                register
+            };
+        };
+    })
+}
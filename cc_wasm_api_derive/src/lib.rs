@@ -0,0 +1,532 @@
+//! derive macros and the `cc_export` attribute for [cc_wasm_api](https://docs.rs/cc_wasm_api),
+//! use them through `cc_wasm_api` with the `derive` feature.
+
+mod class;
+mod export;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, parse_macro_input, parse_quote, Attribute, Data, DataEnum, DeriveInput, Fields,
+    Generics, Ident, LitStr, Path, Type,
+};
+
+/// derive `Importable`.
+///
+/// - structs with named fields are imported from a table, keyed by the field name
+/// - tuple structs are imported as positional values, like a tuple
+/// - enums without fields are imported from a string, the variant name
+///
+/// supported attributes:
+/// - `#[lua(rename_all = "...")]` on the type, one of `lowercase`, `UPPERCASE`, `camelCase`,
+///   `PascalCase`, `snake_case`, `SCREAMING_SNAKE_CASE`
+/// - `#[lua(rename = "...")]` on a field or variant
+/// - `#[lua(default)]` or `#[lua(default = "path::to::fn")]` on a field, used when the key is missing.
+///   `Option` fields are `None` when missing
+/// - `#[lua(skip)]` on a field, it is never imported or exported and uses [Default]
+#[proc_macro_derive(Importable, attributes(lua))]
+pub fn derive_importable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, Trait::Import)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// derive `Exportable`, see [macro@Importable] for the layout and attributes.
+///
+/// `Option` fields of named structs are left out of the table when they are `None`
+#[proc_macro_derive(Exportable, attributes(lua))]
+pub fn derive_exportable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, Trait::Export)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// export a function to the `cc wasm` mod, from anywhere in the crate.
+///
+/// the function is exported by the `export_func` generated by `export_funcs!`,
+/// so `export_funcs!()` still has to be called once in the crate.
+///
+/// options:
+/// - `name = "..."`, the name in lua, defaults to the function name
+/// - `args(a, b = expr)`, the argument names used in error messages,
+///   defaults to the parameter names. `b = expr` makes `b` optional, `expr` is used when it is `nil`
+///
+/// ```ignore
+/// #[cc_export(name = "setSpeed", args(side, speed = 1.0))]
+/// fn set_speed(side: String, speed: f64) -> LuaResult<()> {
+///     Ok(())
+/// }
+/// ```
+///
+/// the function is registered by a constructor in `.init_array`,
+/// so the host must run the module's constructors (`_initialize`) before calling `export_func`.
+/// running them again, as wasm32-wasip1 does before every exported function, is harmless
+///
+/// on an `impl` block, it implements `LuaClass` instead, the `pub` methods taking `&self`
+/// or `&mut self` become methods of the lua object. `name = "..."` sets the class name
+///
+/// ```ignore
+/// #[cc_export]
+/// impl Counter {
+///     pub fn add(&mut self, n: i32) -> i32 {
+///         self.0 += n;
+///         self.0
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn cc_export(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut opts = export::ExportOpts::default();
+    let parser = syn::meta::parser(|meta| opts.parse(meta));
+    parse_macro_input!(attr with parser);
+    let result = match parse_macro_input!(item as syn::Item) {
+        syn::Item::Fn(func) => export::expand(opts, func),
+        syn::Item::Impl(item) => class::expand(opts, item),
+        item => Err(syn::Error::new_spanned(
+            item,
+            "cc_export can only be used on functions and impl blocks",
+        )),
+    };
+    result.unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Trait {
+    Import,
+    Export,
+}
+
+fn expand(input: DeriveInput, tr: Trait) -> syn::Result<TokenStream2> {
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let name = &input.ident;
+    let body = match (&input.data, tr) {
+        (Data::Struct(s), Trait::Import) => import_struct(&s.fields, &container)?,
+        (Data::Struct(s), Trait::Export) => export_struct(&s.fields, &container)?,
+        (Data::Enum(e), Trait::Import) => import_enum(name, e, &container)?,
+        (Data::Enum(e), Trait::Export) => export_enum(e, &container)?,
+        (Data::Union(_), _) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "unions can not be imported or exported",
+            ))
+        }
+    };
+    let trait_path: Path = match tr {
+        Trait::Import => parse_quote!(::cc_wasm_api::lua_api::Importable),
+        Trait::Export => parse_quote!(::cc_wasm_api::lua_api::Exportable),
+    };
+    let generics = add_bounds(input.generics.clone(), &trait_path);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let lua_type = match &input.data {
+        Data::Struct(s) => struct_lua_type(&s.fields, &container, &trait_path, tr)?,
+        Data::Enum(e) => enum_lua_type(e, &container)?,
+        Data::Union(_) => unreachable!(),
+    };
+    let method = match tr {
+        Trait::Import => quote! {
+            fn import() -> ::cc_wasm_api::lua_api::LuaResult<Self> {
+                #body
+            }
+            fn lua_import_type() -> ::std::string::String {
+                #lua_type
+            }
+        },
+        Trait::Export => quote! {
+            fn export(&self) {
+                #body
+            }
+            fn lua_export_type() -> ::std::string::String {
+                #lua_type
+            }
+        },
+    };
+    Ok(quote! {
+        impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+            #method
+        }
+    })
+}
+
+fn add_bounds(mut generics: Generics, bound: &Path) -> Generics {
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
+
+fn import_struct(fields: &Fields, container: &ContainerAttrs) -> syn::Result<TokenStream2> {
+    Ok(match fields {
+        Fields::Named(named) => {
+            let mut slots = Vec::new();
+            let mut arms = Vec::new();
+            let mut inits = Vec::new();
+            for field in &named.named {
+                let attrs = FieldAttrs::parse(&field.attrs)?;
+                let ident = field.ident.as_ref().unwrap();
+                if attrs.skip {
+                    inits.push(quote!(#ident: ::core::default::Default::default()));
+                    continue;
+                }
+                let ty = &field.ty;
+                let slot = format_ident!("__field_{}", ident.unraw());
+                let key = container.key_of(ident, &attrs);
+                let missing = match attrs.default {
+                    Some(Some(path)) => quote!(#path()),
+                    Some(None) => quote!(::core::default::Default::default()),
+                    None if is_option(ty) => quote!(::core::option::Option::None),
+                    None => quote! {
+                        return ::core::result::Result::Err(
+                            ::cc_wasm_api::lua_api::LuaError::from_str(
+                                ::core::concat!("missing field `", #key, "`"),
+                            ),
+                        )
+                    },
+                };
+                slots.push(quote! {
+                    let mut #slot: ::core::option::Option<#ty> = ::core::option::Option::None;
+                });
+                arms.push(quote! {
+                    ::core::option::Option::Some(#key) => {
+                        #slot = ::core::option::Option::Some(
+                            <#ty as ::cc_wasm_api::lua_api::Importable>::import()?,
+                        );
+                    }
+                });
+                inits.push(quote! {
+                    #ident: match #slot {
+                        ::core::option::Option::Some(v) => v,
+                        ::core::option::Option::None => #missing,
+                    }
+                });
+            }
+            quote! {
+                #(#slots)*
+                ::cc_wasm_api::lua_api::import_table(|| {
+                    let key = <::cc_wasm_api::lua_api::value::LuaValue
+                        as ::cc_wasm_api::lua_api::Importable>::import()?;
+                    match key.as_str() {
+                        #(#arms)*
+                        _ => {
+                            <::cc_wasm_api::lua_api::value::LuaValue
+                                as ::cc_wasm_api::lua_api::Importable>::import()?;
+                        }
+                    }
+                    ::core::result::Result::Ok(())
+                })?;
+                ::core::result::Result::Ok(Self { #(#inits),* })
+            }
+        }
+        Fields::Unnamed(unnamed) => {
+            let mut inits = Vec::new();
+            for field in &unnamed.unnamed {
+                let attrs = FieldAttrs::parse(&field.attrs)?;
+                let ty = &field.ty;
+                inits.push(if attrs.skip {
+                    quote!(::core::default::Default::default())
+                } else {
+                    quote!(<#ty as ::cc_wasm_api::lua_api::Importable>::import()?)
+                });
+            }
+            quote!(::core::result::Result::Ok(Self(#(#inits),*)))
+        }
+        Fields::Unit => quote! {
+            <() as ::cc_wasm_api::lua_api::Importable>::import()?;
+            ::core::result::Result::Ok(Self)
+        },
+    })
+}
+
+fn export_struct(fields: &Fields, container: &ContainerAttrs) -> syn::Result<TokenStream2> {
+    Ok(match fields {
+        Fields::Named(named) => {
+            let mut pairs = Vec::new();
+            for field in &named.named {
+                let attrs = FieldAttrs::parse(&field.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let ident = field.ident.as_ref().unwrap();
+                let key = container.key_of(ident, &attrs);
+                pairs.push(if is_option(&field.ty) {
+                    quote! {
+                        if let ::core::option::Option::Some(v) = &self.#ident {
+                            ::cc_wasm_api::lua_api::Exportable::export(#key);
+                            ::cc_wasm_api::lua_api::Exportable::export(v);
+                        }
+                    }
+                } else {
+                    quote! {
+                        ::cc_wasm_api::lua_api::Exportable::export(#key);
+                        ::cc_wasm_api::lua_api::Exportable::export(&self.#ident);
+                    }
+                });
+            }
+            quote! {
+                ::cc_wasm_api::lua_api::export_table(|| {
+                    #(#pairs)*
+                });
+            }
+        }
+        Fields::Unnamed(unnamed) => {
+            let mut exports = Vec::new();
+            for (i, field) in unnamed.unnamed.iter().enumerate() {
+                if FieldAttrs::parse(&field.attrs)?.skip {
+                    continue;
+                }
+                let index = syn::Index::from(i);
+                exports.push(quote!(::cc_wasm_api::lua_api::Exportable::export(&self.#index);));
+            }
+            quote!(#(#exports)*)
+        }
+        Fields::Unit => quote!(),
+    })
+}
+
+/// the EmmyLua type, `{key: type}` for named structs and a union of strings for enums
+fn struct_lua_type(
+    fields: &Fields,
+    container: &ContainerAttrs,
+    trait_path: &Path,
+    tr: Trait,
+) -> syn::Result<TokenStream2> {
+    let type_of = |ty: &Type| match tr {
+        Trait::Import => quote!(<#ty as #trait_path>::lua_import_type()),
+        Trait::Export => quote!(<#ty as #trait_path>::lua_export_type()),
+    };
+    Ok(match fields {
+        Fields::Named(named) => {
+            let mut entries = Vec::new();
+            for field in &named.named {
+                let attrs = FieldAttrs::parse(&field.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let key = container.key_of(field.ident.as_ref().unwrap(), &attrs);
+                let ty = type_of(&field.ty);
+                // fields with a default value can be left out
+                let optional = if attrs.default.is_some() { "?" } else { "" };
+                entries.push(quote!(::std::format!("{}{}: {}", #key, #optional, #ty)));
+            }
+            quote!(::std::format!("{{{}}}", [#(#entries),*].join(", ")))
+        }
+        Fields::Unnamed(unnamed) => {
+            let mut types = Vec::new();
+            for field in &unnamed.unnamed {
+                if !FieldAttrs::parse(&field.attrs)?.skip {
+                    types.push(type_of(&field.ty));
+                }
+            }
+            quote!(<[::std::string::String]>::join(&[#(#types),*], ", "))
+        }
+        Fields::Unit => match tr {
+            Trait::Import => quote!(::std::string::String::from("nil")),
+            Trait::Export => quote!(::std::string::String::new()),
+        },
+    })
+}
+
+fn enum_lua_type(data: &DataEnum, container: &ContainerAttrs) -> syn::Result<TokenStream2> {
+    let literals = unit_variants(data, container)?
+        .into_iter()
+        .map(|(_, name)| format!("{name:?}"))
+        .collect::<Vec<_>>()
+        .join("|");
+    Ok(quote!(::std::string::String::from(#literals)))
+}
+
+fn unit_variants<'a>(
+    data: &'a DataEnum,
+    container: &'a ContainerAttrs,
+) -> syn::Result<Vec<(&'a Ident, String)>> {
+    data.variants
+        .iter()
+        .map(|variant| {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "only enums without fields can be imported or exported",
+                ));
+            }
+            let attrs = FieldAttrs::parse(&variant.attrs)?;
+            if attrs.skip || attrs.default.is_some() {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "`skip` and `default` are not supported on variants",
+                ));
+            }
+            let name = match attrs.rename {
+                Some(name) => name,
+                None => container.rename_all.apply(&variant.ident.to_string()),
+            };
+            Ok((&variant.ident, name))
+        })
+        .collect()
+}
+
+fn import_enum(
+    name: &Ident,
+    data: &DataEnum,
+    container: &ContainerAttrs,
+) -> syn::Result<TokenStream2> {
+    let (idents, names): (Vec<_>, Vec<_>) = unit_variants(data, container)?.into_iter().unzip();
+    let type_name = name.to_string();
+    Ok(quote! {
+        let s = <::std::string::String as ::cc_wasm_api::lua_api::Importable>::import()?;
+        match s.as_str() {
+            #(#names => ::core::result::Result::Ok(Self::#idents),)*
+            _ => ::core::result::Result::Err(::cc_wasm_api::lua_api::LuaError::from_string(
+                ::std::format!("invalid {}: {}", #type_name, s),
+            )),
+        }
+    })
+}
+
+fn export_enum(data: &DataEnum, container: &ContainerAttrs) -> syn::Result<TokenStream2> {
+    let (idents, names): (Vec<_>, Vec<_>) = unit_variants(data, container)?.into_iter().unzip();
+    Ok(quote! {
+        match self {
+            #(Self::#idents => ::cc_wasm_api::lua_api::Exportable::export(#names),)*
+        }
+    })
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p.path.segments.last().is_some_and(|s| s.ident == "Option"),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct ContainerAttrs {
+    rename_all: RenameRule,
+}
+impl ContainerAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut out = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("lua")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    let rule: LitStr = meta.value()?.parse()?;
+                    out.rename_all = RenameRule::from_str(&rule.value())
+                        .ok_or_else(|| meta.error("unknown `rename_all` rule"))?;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown lua attribute"))
+                }
+            })?;
+        }
+        Ok(out)
+    }
+    fn key_of(&self, ident: &Ident, attrs: &FieldAttrs) -> String {
+        match &attrs.rename {
+            Some(name) => name.clone(),
+            None => self.rename_all.apply(&ident.unraw().to_string()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    /// `Some(None)` uses [Default], `Some(Some(path))` calls `path()`
+    default: Option<Option<Path>>,
+    skip: bool,
+}
+impl FieldAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut out = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("lua")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    out.rename = Some(name.value());
+                } else if meta.path.is_ident("default") {
+                    out.default = Some(if meta.input.peek(syn::Token![=]) {
+                        let path: LitStr = meta.value()?.parse()?;
+                        Some(path.parse()?)
+                    } else {
+                        None
+                    });
+                } else if meta.path.is_ident("skip") {
+                    out.skip = true;
+                } else {
+                    return Err(meta.error("unknown lua attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+enum RenameRule {
+    #[default]
+    None,
+    Lower,
+    Upper,
+    Camel,
+    Pascal,
+    Snake,
+    ScreamingSnake,
+}
+impl RenameRule {
+    fn from_str(s: &str) -> Option<Self> {
+        Some(match s {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "camelCase" => Self::Camel,
+            "PascalCase" => Self::Pascal,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            _ => None?,
+        })
+    }
+    fn apply(self, name: &str) -> String {
+        let words = words(name);
+        let capitalize = |w: &String| {
+            let mut c = w.chars();
+            c.next()
+                .map(|f| f.to_uppercase().chain(c).collect())
+                .unwrap_or_default()
+        };
+        match self {
+            Self::None => name.to_owned(),
+            Self::Lower => words.concat(),
+            Self::Upper => words.concat().to_uppercase(),
+            Self::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+                .collect(),
+            Self::Pascal => words.iter().map(capitalize).collect(),
+            Self::Snake => words.join("_"),
+            Self::ScreamingSnake => words.join("_").to_uppercase(),
+        }
+    }
+}
+/// split `snake_case` or `PascalCase` into lowercase words
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for c in name.chars() {
+        if c == '_' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
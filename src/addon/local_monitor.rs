@@ -4,7 +4,7 @@ mod drawing;
 mod functions;
 mod initing;
 
-use crate::{eval::exec, prelude::LuaResult};
+use crate::{eval::exec, lua_api::lua_ref::LuaRef, prelude::LuaResult};
 
 use super::{
     misc::{AsIfPixel, ColorId, Direction},
@@ -22,6 +22,12 @@ pub struct LocalMonitor {
     pub(crate) last_sync: Vec2d<AsIfPixel>,
     // pub(crate) side: Side,
     pub(crate) name: String,
+    /// the wrapped peripheral, `None` if not inited or the host does not support handles.
+    ///
+    /// only used to query the size, the drawing scripts address the monitor by its global
+    /// `global.<name>`, since [sync_script](LocalMonitor::sync_script) hands them to the
+    /// caller to be run together with other scripts, and hosts without handles need it too
+    pub(crate) peripheral: Option<LuaRef>,
     // pub(crate) is_remote: bool,
     // pub(crate) remote_name: Option<String>,
 }
@@ -41,9 +47,16 @@ impl LocalMonitor {
 
             // side: Side::Top,
             name: String::new(),
+            peripheral: None,
         }
     }
-    fn new(x: usize, y: usize, pixel: AsIfPixel, init_method: InitMethod) -> Self {
+    fn new(
+        x: usize,
+        y: usize,
+        pixel: AsIfPixel,
+        init_method: InitMethod,
+        peripheral: Option<LuaRef>,
+    ) -> Self {
         Self {
             data: Vec2d::new_filled_copy(x, y, pixel),
             last_sync: Vec2d::new_filled_copy(
@@ -55,6 +68,7 @@ impl LocalMonitor {
             ),
             // side,
             name: LocalMonitor::gen_name(init_method),
+            peripheral,
         }
     }
     fn resize(&mut self, x: usize, y: usize, pixel: AsIfPixel) {
@@ -1,7 +1,7 @@
 use crate::{
     addon::misc::{AsIfPixel, ColorId},
     debug::{self, show_str},
-    eval::exec,
+    eval::{exec_traced, EvalError},
     prelude::LuaResult,
 };
 
@@ -288,6 +288,14 @@ impl LocalMonitor {
 
 // const BATCH: usize = 20000;
 impl LocalMonitor {
+    fn report_draw_error(&self, e: EvalError, script: &str) -> EvalError {
+        debug::show_str(&format!(
+            "monitor [{}] draw failed: {e}\nat: {}",
+            self.name(),
+            e.source_line(script).unwrap_or("?")
+        ));
+        e
+    }
     pub async fn sync(&mut self) -> LuaResult<usize> {
         // show_str(self.name());
         let to_write: Vec<(usize, usize, AsIfPixel)> = self.gen_nonsynced();
@@ -300,7 +308,9 @@ impl LocalMonitor {
         let mut write_script = String::new();
         let code_line = self.gen_draw_opt_cursor_long_str(&mut write_script, to_write, true);
 
-        exec(&write_script).await?;
+        exec_traced(&write_script)
+            .await
+            .map_err(|e| self.report_draw_error(e, &write_script))?;
         debug::show_str(&format!(
             "monitor [{}] draw code line: {}, changed pix: {}",
             self.name(),
@@ -323,7 +333,9 @@ impl LocalMonitor {
         let mut write_script = String::new();
         let code_line = self.gen_draw_opt_clear(&mut write_script, bg_color);
 
-        exec(&write_script).await?;
+        exec_traced(&write_script)
+            .await
+            .map_err(|e| self.report_draw_error(e, &write_script))?;
         debug::show_str(&format!(
             "monitor [{}] draw code line: {}, changed pix: {}",
             self.name(),
@@ -1,16 +1,30 @@
 use crate::{
     addon::misc::{AsIfPixel, ColorId, Side},
-    eval::{eval, exec},
+    cc_mod::host::{has_capability, Capability},
+    eval::{eval, eval_with, exec},
+    lua_api::{lua_ref::LuaRef, LuaError},
     prelude::LuaResult,
     utils::Number,
 };
 
 use super::LocalMonitor;
 impl LocalMonitor {
+    /// the name of the global holding the peripheral, a valid lua identifier.
+    ///
+    /// bytes of a remote name other than ascii letters and digits are written as `_xx` in hex,
+    /// so different names never share a global
     pub(crate) fn gen_name(init_method: InitMethod<'_>) -> String {
         match init_method {
             InitMethod::Remote { side, name } => {
-                format!("monitor_remote_{s}_{n}", s = side.name(), n = name)
+                let mut n = String::with_capacity(name.len());
+                for b in name.bytes() {
+                    if b.is_ascii_alphanumeric() {
+                        n.push(b as char);
+                    } else {
+                        n += &format!("_{b:02x}");
+                    }
+                }
+                format!("monitor_remote_{s}_{n}", s = side.name())
             }
             InitMethod::Local(side) => format!("monitor_local_{s}", s = side.name()),
         }
@@ -52,25 +66,57 @@ impl LocalMonitor {
     }
     pub async fn new_inited(init_method: impl Into<InitMethod<'_>>) -> LuaResult<Self> {
         let init_method = init_method.into();
-        exec(&Self::gen_script_init_monitor(init_method)).await?;
-        let (x, y): (Number, Number) = eval(&format!(
-            "return {}.getSize()",
-            LocalMonitor::gen_name(init_method)
-        ))
-        .await?;
-        let (x, y) = (x.to_i32() as usize, y.to_i32() as usize);
+        let global = LocalMonitor::gen_name(init_method);
+        // hosts without these only run plain scripts, the monitor is reached by its global
+        let peripheral = if has_capability(Capability::EvalArgs)
+            && has_capability(Capability::Handles)
+            && has_capability(Capability::Calls)
+        {
+            let peripheral: LuaRef = match init_method {
+                InitMethod::Remote { side, name } => {
+                    let script = concat!(
+                        include_str!("wrap_peri.lua"),
+                        "\nlocal global_name, side, name = ...",
+                        "\nglobal[global_name] = wrap_remote(side, name)",
+                        "\nreturn global[global_name]",
+                    );
+                    eval_with(script, (global.as_str(), side.name(), name)).await?
+                }
+                InitMethod::Local(side) => {
+                    let script = "local global_name, side = ...\n\
+                        global[global_name] = peripheral.wrap(side)\n\
+                        return global[global_name]";
+                    eval_with(script, (global.as_str(), side.name())).await?
+                }
+            };
+            Some(peripheral)
+        } else {
+            exec(&Self::gen_script_init_monitor(init_method)).await?;
+            None
+        };
+        let (x, y) = Self::query_size(&global, peripheral.as_ref()).await?;
 
-        let mut new_self = Self::new(x, y, AsIfPixel::default(), init_method);
+        let mut new_self = Self::new(x, y, AsIfPixel::default(), init_method, peripheral);
         new_self
             .clear(AsIfPixel::default().background_color)
             .await?;
 
         Ok(new_self)
     }
+    /// `getSize` of the monitor, through `peripheral` if the host supports handles
+    async fn query_size(name: &str, peripheral: Option<&LuaRef>) -> LuaResult<(usize, usize)> {
+        let (x, y): (Number, Number) = match peripheral {
+            Some(peripheral) => peripheral.call_method("getSize", ()).await?,
+            None => eval(&format!("return {name}.getSize()")).await?,
+        };
+        Ok((x.to_i32() as usize, y.to_i32() as usize))
+    }
     /// returns if resized
     pub async fn sync_size(&mut self) -> LuaResult<bool> {
-        let (x, y): (Number, Number) = eval(&format!("return {}.getSize()", self.name())).await?;
-        let (x, y) = (x.to_i32() as usize, y.to_i32() as usize);
+        if self.name.is_empty() {
+            return Err(LuaError::from_str("monitor is not inited"));
+        }
+        let (x, y) = Self::query_size(&self.name, self.peripheral.as_ref()).await?;
         if self.size() == (x, y) {
             return Ok(false);
         }
@@ -1,7 +1,14 @@
 use std::{fmt::Display, future::Future};
 
-use crate::{debug::show_str, eval::exec, lua_api::LuaError};
+use crate::{
+    cc_mod::host::{has_capability, Capability},
+    debug::show_str,
+    eval::{exec, exec_with},
+    lua_api::LuaError,
+};
 
+/// raises the error in lua, the message is passed with `exec_with` if the host has
+/// [EvalArgs](Capability::EvalArgs), otherwise it is formatted into the script
 pub trait Throw<T> {
     fn throw(self) -> impl Future<Output = T>;
     fn throw_with_info(self, file_line: (&'static str, u32)) -> impl Future<Output = T>;
@@ -34,12 +41,14 @@ macro_rules! throw_exec {
         $crate::addon::throw::Throw::throw_with_info(e, (::std::file!(), ::std::line!())).await
     }};
 }
+/// the values are formatted into the script, prefer [throw_exec_with](crate::throw_exec_with) for strings
 #[macro_export]
 macro_rules! exec_format {
     ($($t:tt)*) => {
         $crate::throw_exec!(&::std::format!($($t)*));
     };
 }
+/// the values are formatted into the script, prefer [throw_eval_with](crate::throw_eval_with) for strings
 #[macro_export]
 macro_rules! eval_format {
     ($($t:tt)*) => {
@@ -66,6 +75,37 @@ macro_rules! throw_eval {
     }};
 }
 
+/// `throw_exec_with!(script, args)`, `args` are passed to the script as `...`
+#[macro_export]
+macro_rules! throw_exec_with {
+    ($e:expr, $args:expr) => {{
+        let e = $crate::eval::exec_with($e, $args).await;
+        $crate::addon::throw::Throw::throw_with_info(e, (::std::file!(), ::std::line!())).await
+    }};
+}
+/// `throw_eval_with!(script, args)` or `throw_eval_with!(Type, script, args)`,
+/// `args` are passed to the script as `...`
+#[macro_export]
+macro_rules! throw_eval_with {
+    ($t:ty, $e:expr, $args:expr) => {{
+        let e = $crate::eval::eval_with::<$t, _>($e, $args).await;
+        $crate::addon::throw::Throw::throw_with_info(e, (::std::file!(), ::std::line!())).await
+    }};
+    ($e:expr, $args:expr) => {{
+        let e = $crate::eval::eval_with($e, $args).await;
+        $crate::addon::throw::Throw::throw_with_info(e, (::std::file!(), ::std::line!())).await
+    }};
+}
+
+/// raises `msg` as a lua error, the script path is kept for hosts without `EvalArgs`
+async fn raise(msg: String) {
+    if has_capability(Capability::EvalArgs) {
+        exec_with("error(...)", msg).await.unwrap();
+    } else {
+        exec(&format!("error({:?})", msg)).await.unwrap();
+    }
+}
+
 impl<T, E: Display> Throw<T> for Result<T, E> {
     async fn throw(self) -> T {
         match self {
@@ -73,9 +113,8 @@ impl<T, E: Display> Throw<T> for Result<T, E> {
             Err(e) => {
                 let msg = format!("{}", e);
                 // let msg = format!("{}\nbacktrace:\n{}", e, Backtrace::capture());
-                let script = &format!("error({:?})", msg);
-                show_str(script);
-                exec(script).await.unwrap();
+                show_str(&msg);
+                raise(msg).await;
                 panic!()
             }
         }
@@ -87,9 +126,8 @@ impl<T, E: Display> Throw<T> for Result<T, E> {
             Err(e) => {
                 let msg = format!("{file}:{line}\n{}", e);
                 // let msg = format!("{}\nbacktrace:\n{}", e, Backtrace::capture());
-                let script = &format!("error({:?})", msg);
-                show_str(script);
-                exec(script).await.unwrap();
+                show_str(&msg);
+                raise(msg).await;
                 panic!()
             }
         }
@@ -103,9 +141,8 @@ impl<T> Throw<T> for Result<T, LuaError> {
             Err(e) => {
                 let msg = e.0.into_owned();
                 // let msg = format!("{}\nbacktrace:\n{}", e.as_str(), Backtrace::capture());
-                let script = &format!("error({:?})", msg);
-                show_str(script);
-                exec(script).await.unwrap();
+                show_str(&msg);
+                raise(msg).await;
                 panic!()
             }
         }
@@ -116,9 +153,8 @@ impl<T> Throw<T> for Result<T, LuaError> {
             Err(e) => {
                 let msg = format!("{file}:{line}\n{}", e.as_str());
                 // let msg = format!("{}\nbacktrace:\n{}", e, Backtrace::capture());
-                let script = &format!("error({:?})", msg);
-                show_str(script);
-                exec(script).await.unwrap();
+                show_str(&msg);
+                raise(msg).await;
                 panic!()
             }
         }
@@ -1,7 +1,25 @@
+#[cfg(feature = "coroutine")]
+use std::future::Future;
+
+#[cfg(feature = "coroutine")]
+use crate::utils::{ImplAsyncResult, ImplAsyncValue};
 use crate::{
-    lua_api::{failed, success, Exportable, Importable, LuaResult},
+    lua_api::{failed, success, Exportable, Importable, LuaError, LuaResult},
     utils::{ImplResult, ImplValue},
 };
+
+/// `async fn`s can be exported too. they return right away and
+/// their future is spawned on the coroutine runtime,
+/// wrap the module with [ASYNC_SHIM](async_export::ASYNC_SHIM) on the lua side to wait for the result
+#[cfg(feature = "coroutine")]
+#[cfg_attr(docsrs, doc(cfg(feature = "coroutine")))]
+pub mod async_export;
+/// the host ABI version and optional host features, see [Capability](host::Capability)
+pub mod host;
+/// rust objects whose methods are called from lua, see [LuaClass](object::LuaClass)
+pub mod object;
+/// panics are reported to lua instead of trapping silently, see [install_hook](panic::install_hook)
+pub mod panic;
 /// used to run this function with cc_wasm mod
 /// # Safety
 /// should not be manually impled
@@ -9,18 +27,206 @@ pub unsafe trait ExportFunc<Args, Out, ImplType> {
     /// used to run this function with cc_wasm mod
     /// # Safety
     /// should not be manually called
-    unsafe fn call(&self);
+    unsafe fn call(&self, info: &ExportInfo);
+    /// the EmmyLua annotated stub of this function, see [describe_function]
+    fn describe(&self, info: &ExportInfo) -> String;
+}
+
+/// an EmmyLua (LuaLS) annotated stub of an exported function, as a field of the module table `M`.
+///
+/// `params` are the [Importable::lua_import_type]s, parameters without names
+/// in `info.args` are named `argN`; `returns` is the [Exportable::lua_export_type]
+pub fn describe_function(
+    info: &ExportInfo,
+    params: &[String],
+    returns: &str,
+    is_async: bool,
+) -> String {
+    let mut names = Vec::new();
+    let mut types = Vec::new();
+    for (i, ty) in params.iter().enumerate() {
+        let name = match info.args.get(i) {
+            Some(name) => name.to_string(),
+            None => format!("arg{}", i + 1),
+        };
+        // tuples take several lua values
+        let parts = split_types(ty);
+        if parts.len() == 1 {
+            names.push(name);
+            types.push(ty.as_str());
+        } else {
+            for (k, part) in parts.into_iter().enumerate() {
+                names.push(format!("{name}_{}", k + 1));
+                types.push(part);
+            }
+        }
+    }
+    let mut stub = String::new();
+    if is_async {
+        stub += "---@async\n";
+    }
+    for (name, ty) in names.iter().zip(types) {
+        stub += &format!("---@param {name} {ty}\n");
+    }
+    if !returns.is_empty() {
+        stub += &format!("---@return {returns}\n");
+    }
+    stub += &format!("function M.{}({}) end\n", info.name, names.join(", "));
+    stub
+}
+
+/// splits `a, table<b, c>` into `a` and `table<b, c>`
+fn split_types(types: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in types.char_indices() {
+        match c {
+            '<' | '{' | '(' => depth += 1,
+            '>' | '}' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(types[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(types[start..].trim());
+    parts
+}
+
+/// the stub file of the whole module, for the lua language server.
+///
+/// `functions` are the stubs made by [describe_function]
+pub fn describe_module(functions: impl IntoIterator<Item = String>) -> String {
+    let mut stub = String::from("---@meta\n\nlocal M = {}\n\n");
+    for function in functions {
+        stub += &function;
+        stub += "\n";
+    }
+    stub += "return M\n";
+    stub
+}
+
+/// the name and parameter names of an exported function, used in error messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExportInfo {
+    pub name: &'static str,
+    /// can be shorter than the parameter list, or empty
+    pub args: &'static [&'static str],
+}
+impl ExportInfo {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, args: &[] }
+    }
+    pub const fn with_args(self, args: &'static [&'static str]) -> Self {
+        Self { args, ..self }
+    }
+    /// `bad argument 'arg' to 'name' (err)`, or `bad parameter #n of 'name' (err)` if the
+    /// parameter has no name.
+    ///
+    /// `n` is the position of the rust parameter starting with 1, not a lua argument position,
+    /// a parameter such as a tuple can take several lua values
+    pub fn bad_argument(&self, n: usize, err: LuaError) -> LuaError {
+        match self.args.get(n - 1) {
+            Some(arg) => LuaError::from_string(format!(
+                "bad argument '{arg}' to '{}' ({})",
+                self.name,
+                err.as_str()
+            )),
+            None => LuaError::from_string(format!(
+                "bad parameter #{n} of '{}' ({})",
+                self.name,
+                err.as_str()
+            )),
+        }
+    }
+}
+
+/// functions registered by `#[cc_export]`, exported by [lib_exports](crate::lib_exports)
+#[doc(hidden)]
+pub mod registry {
+    use std::cell::Cell;
+
+    use super::ExportInfo;
+    use crate::utils::SyncNonSync;
+
+    /// a registered function, linked into a list when its constructor runs
+    pub struct Registered {
+        pub info: ExportInfo,
+        /// [ExportFunc::describe](super::ExportFunc::describe) of the function
+        pub describe: fn(&ExportInfo) -> String,
+        next: SyncNonSync<Cell<Option<&'static Registered>>>,
+        linked: SyncNonSync<Cell<bool>>,
+    }
+    impl Registered {
+        pub const fn new(info: ExportInfo, describe: fn(&ExportInfo) -> String) -> Self {
+            Self {
+                info,
+                describe,
+                next: SyncNonSync(Cell::new(None)),
+                linked: SyncNonSync(Cell::new(false)),
+            }
+        }
+    }
+
+    static HEAD: SyncNonSync<Cell<Option<&'static Registered>>> = SyncNonSync(Cell::new(None));
+
+    /// called from the generated constructors, before any exported function.
+    ///
+    /// on wasm32-wasip1 the constructors run again before every exported function,
+    /// an entry which is already linked is left as it is
+    pub fn submit(registered: &'static Registered) {
+        if registered.linked.replace(true) {
+            return;
+        }
+        registered.next.set(HEAD.get());
+        HEAD.set(Some(registered));
+    }
+    pub fn registered() -> impl Iterator<Item = &'static Registered> {
+        std::iter::successors(HEAD.get(), |r| r.next.get())
+    }
+    pub fn describe_registered() -> impl Iterator<Item = String> {
+        registered().map(|r| (r.describe)(&r.info))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn submit_twice() {
+            static ENTRY: Registered =
+                Registered::new(ExportInfo::new("submit_twice"), |_| String::new());
+            submit(&ENTRY);
+            submit(&ENTRY);
+            let names: Vec<_> = registered().map(|r| r.info.name).collect();
+            assert_eq!(names, ["submit_twice"]);
+        }
+    }
 }
 
 /// export functions to the `cc wasm` mod.
 ///
-/// usage: `export_funcs!((fn1, export_name1), (fn2, export_name2))` or `export_funcs!(fn1, fn2, fn3)`
+/// usage: `export_funcs!((fn1, export_name1), (fn2, export_name2))` or `export_funcs!(fn1, fn2, fn3)`.
+///
+/// parameter names can be added for error messages: `export_funcs!((set_speed, set_speed, [side, speed]))`
 ///
 /// the function's arguments must be [Importable], and
-/// the function's return value must be [Exportable] or [LuaResult]<[Exportable]>
+/// the function's return value must be [Exportable] or [LuaResult]<[Exportable]>,
+/// or a [Future] of them (see [async_export])
+///
+/// functions marked with `#[cc_export]` are also exported, so `export_funcs!()`
+/// is enough when every function uses the attribute
+///
+/// the host may pass its ABI version and capabilities to `export_func`,
+/// see [negotiate](host::negotiate)
+///
+/// a `describe_exports` function is exported too, it returns a EmmyLua stub file of
+/// every exported function (see [describe_module]), save it as a `.lua` file
+/// in the workspace for the lua language server to autocomplete the module
 #[macro_export]
 macro_rules! export_funcs {
-    ($(($f:ident, $ename:ident)),*) => {
+    ($(($f:ident, $ename:ident $(, [$($arg:ident),*])?)),*) => {
        const _:() = {
         mod inner{
             $(
@@ -28,16 +234,41 @@ macro_rules! export_funcs {
                 #[no_mangle]
                 pub extern "C" fn $ename(){
                     use super::$f;
-                    unsafe { $crate::cc_mod::ExportFunc::call(&$f); }
+                    if $crate::cc_mod::panic::report_poisoned() {
+                        return;
+                    }
+                    const INFO: $crate::cc_mod::ExportInfo =
+                        $crate::cc_mod::ExportInfo::new(::core::stringify!($ename))
+                            .with_args(&[$($(::core::stringify!($arg)),*)?]);
+                    unsafe { $crate::cc_mod::ExportFunc::call(&$f, &INFO); }
                 }
             )*
 
             #[no_mangle]
             pub extern "C" fn export_func() {
+                if !$crate::cc_mod::host::negotiate() {
+                    return;
+                }
                 unsafe { $crate::lib_exports(); }
+                $crate::lua_api::Exportable::export("describe_exports");
                 $(
                     $crate::lua_api::Exportable::export(::core::stringify!($ename));
                 )*
+            }
+
+            #[no_mangle]
+            pub extern "C" fn describe_exports() {
+                let functions = [$(
+                    $crate::cc_mod::ExportFunc::describe(
+                        &super::$f,
+                        &$crate::cc_mod::ExportInfo::new(::core::stringify!($ename))
+                            .with_args(&[$($(::core::stringify!($arg)),*)?]),
+                    ),
+                )*];
+                let stub = $crate::cc_mod::describe_module(
+                    functions.into_iter().chain($crate::cc_mod::registry::describe_registered()),
+                );
+                $crate::lua_api::Exportable::export(&stub);
             }};
         };
 
@@ -50,16 +281,39 @@ macro_rules! export_funcs {
                 #[no_mangle]
                 pub extern "C" fn $f(){
                     use super::$f;
-                    unsafe { $crate::cc_mod::ExportFunc::call(&$f); }
+                    if $crate::cc_mod::panic::report_poisoned() {
+                        return;
+                    }
+                    const INFO: $crate::cc_mod::ExportInfo =
+                        $crate::cc_mod::ExportInfo::new(::core::stringify!($f));
+                    unsafe { $crate::cc_mod::ExportFunc::call(&$f, &INFO); }
                 }
             )*
 
             #[no_mangle]
             pub extern "C" fn export_func() {
+                if !$crate::cc_mod::host::negotiate() {
+                    return;
+                }
                 unsafe { $crate::lib_exports(); }
+                $crate::lua_api::Exportable::export("describe_exports");
                 $(
                     $crate::lua_api::Exportable::export(::core::stringify!($f));
                 )*
+            }
+
+            #[no_mangle]
+            pub extern "C" fn describe_exports() {
+                let functions = [$(
+                    $crate::cc_mod::ExportFunc::describe(
+                        &super::$f,
+                        &$crate::cc_mod::ExportInfo::new(::core::stringify!($f)),
+                    ),
+                )*];
+                let stub = $crate::cc_mod::describe_module(
+                    functions.into_iter().chain($crate::cc_mod::registry::describe_registered()),
+                );
+                $crate::lua_api::Exportable::export(&stub);
             }};
         };
 
@@ -75,11 +329,16 @@ macro_rules! impl_export {
         unsafe impl<$($t: Importable,)* O: Exportable, F: Fn($($t),*) -> LuaResult<O>>
             ExportFunc<($($t,)*), O, ImplResult> for F
         {
-            unsafe fn call(&self) {
+            fn describe(&self, info: &ExportInfo) -> String {
+                describe_function(info, &[$($t::lua_import_type()),*], &O::lua_export_type(), false)
+            }
+            unsafe fn call(&self, info: &ExportInfo) {
                 let o: LuaResult<O> = (|| {
+                    let mut n = 0;
                     $(
+                        n += 1;
                         #[allow(non_snake_case)]
-                        let $t = $t::import()?;
+                        let $t = $t::import().map_err(|e| info.bad_argument(n, e))?;
                     )*
                     let out = self($($t),*)?;
 
@@ -101,11 +360,16 @@ macro_rules! impl_export {
         unsafe impl<$($t: Importable,)* O: Exportable, F: Fn($($t),*) -> O>
             ExportFunc<($($t,)*), O, ImplValue> for F
         {
-            unsafe fn call(&self) {
+            fn describe(&self, info: &ExportInfo) -> String {
+                describe_function(info, &[$($t::lua_import_type()),*], &O::lua_export_type(), false)
+            }
+            unsafe fn call(&self, info: &ExportInfo) {
                 let o: LuaResult<O> = (|| {
+                    let mut n = 0;
                     $(
+                        n += 1;
                         #[allow(non_snake_case)]
-                        let $t = $t::import()?;
+                        let $t = $t::import().map_err(|e| info.bad_argument(n, e))?;
                     )*
                     let out = self($($t),*);
 
@@ -126,7 +390,10 @@ macro_rules! impl_export {
     };
 }
 unsafe impl<O: Exportable, F: Fn() -> LuaResult<O>> ExportFunc<(), O, ImplResult> for F {
-    unsafe fn call(&self) {
+    fn describe(&self, info: &ExportInfo) -> String {
+        describe_function(info, &[], &O::lua_export_type(), false)
+    }
+    unsafe fn call(&self, _info: &ExportInfo) {
         match self() {
             Ok(o) => {
                 unsafe { success() };
@@ -140,13 +407,106 @@ unsafe impl<O: Exportable, F: Fn() -> LuaResult<O>> ExportFunc<(), O, ImplResult
     }
 }
 unsafe impl<O: Exportable, F: Fn() -> O> ExportFunc<(), O, ImplValue> for F {
-    unsafe fn call(&self) {
+    fn describe(&self, info: &ExportInfo) -> String {
+        describe_function(info, &[], &O::lua_export_type(), false)
+    }
+    unsafe fn call(&self, _info: &ExportInfo) {
         let o = self();
         unsafe { success() };
         o.export();
     }
 }
 
+macro_rules! impl_export_async {
+    ($($t:ident),*) => {
+        #[cfg(feature = "coroutine")]
+        unsafe impl<
+                $($t: Importable,)*
+                O: Exportable + 'static,
+                Fut: Future<Output = LuaResult<O>> + 'static,
+                F: Fn($($t),*) -> Fut,
+            > ExportFunc<($($t,)*), O, ImplAsyncResult> for F
+        {
+            fn describe(&self, info: &ExportInfo) -> String {
+                describe_function(info, &[$($t::lua_import_type()),*], &O::lua_export_type(), true)
+            }
+            #[allow(unused_mut, unused_variables, clippy::redundant_closure_call)]
+            unsafe fn call(&self, info: &ExportInfo) {
+                let fut: LuaResult<Fut> = (|| {
+                    let mut n = 0;
+                    $(
+                        n += 1;
+                        #[allow(non_snake_case)]
+                        let $t = $t::import().map_err(|e| info.bad_argument(n, e))?;
+                    )*
+                    Ok(self($($t),*))
+                })();
+                match fut {
+                    Ok(fut) => {
+                        unsafe { success() };
+                        async_export::start(fut);
+                    }
+                    Err(err) => {
+                        unsafe { failed() };
+                        err.as_str().export();
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "coroutine")]
+        unsafe impl<
+                $($t: Importable,)*
+                O: Exportable + 'static,
+                Fut: Future<Output = O> + 'static,
+                F: Fn($($t),*) -> Fut,
+            > ExportFunc<($($t,)*), O, ImplAsyncValue> for F
+        {
+            fn describe(&self, info: &ExportInfo) -> String {
+                describe_function(info, &[$($t::lua_import_type()),*], &O::lua_export_type(), true)
+            }
+            #[allow(unused_mut, unused_variables, clippy::redundant_closure_call)]
+            unsafe fn call(&self, info: &ExportInfo) {
+                let fut: LuaResult<Fut> = (|| {
+                    let mut n = 0;
+                    $(
+                        n += 1;
+                        #[allow(non_snake_case)]
+                        let $t = $t::import().map_err(|e| info.bad_argument(n, e))?;
+                    )*
+                    Ok(self($($t),*))
+                })();
+                match fut {
+                    Ok(fut) => {
+                        unsafe { success() };
+                        async_export::start(async { Ok(fut.await) });
+                    }
+                    Err(err) => {
+                        unsafe { failed() };
+                        err.as_str().export();
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_export_async!();
+impl_export_async!(T0);
+impl_export_async!(T0, T1);
+impl_export_async!(T0, T1, T2);
+impl_export_async!(T0, T1, T2, T3);
+impl_export_async!(T0, T1, T2, T3, T4);
+impl_export_async!(T0, T1, T2, T3, T4, T5);
+impl_export_async!(T0, T1, T2, T3, T4, T5, T6);
+impl_export_async!(T0, T1, T2, T3, T4, T5, T6, T7);
+impl_export_async!(T0, T1, T2, T3, T4, T5, T6, T7, T8);
+impl_export_async!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9);
+impl_export_async!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10);
+impl_export_async!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10, T11);
+impl_export_async!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10, T11, T12);
+impl_export_async!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10, T11, T12, T13);
+
 impl_export!(T0);
 impl_export!(T0, T1);
 impl_export!(T0, T1, T2);
@@ -161,3 +521,22 @@ impl_export!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10);
 impl_export!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10, T11);
 impl_export!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10, T11, T12);
 impl_export!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10, T11, T12, T13);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_argument_names_the_parameter() {
+        let info = ExportInfo::new("move_to").with_args(&["pos"]);
+        let err = || LuaError::from_str("expect I32, got Nil");
+        assert_eq!(
+            info.bad_argument(1, err()).as_str(),
+            "bad argument 'pos' to 'move_to' (expect I32, got Nil)"
+        );
+        assert_eq!(
+            info.bad_argument(2, err()).as_str(),
+            "bad parameter #2 of 'move_to' (expect I32, got Nil)"
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    future::Future,
+};
+
+use crate::{
+    coroutine::spawn,
+    lua_api::{failed, success, Exportable, Importable, LuaError, LuaResult},
+    utils::SyncNonSync,
+};
+
+/// the first value returned by an async exported function, followed by the id
+const ASYNC_MARKER: &str = "__cc_wasm_async";
+/// lua source of the shim which makes async exported functions block until they finish
+pub const ASYNC_SHIM: &str = include_str!("async_shim.lua");
+
+enum Slot {
+    Pending,
+    Ready(Box<dyn Exportable>),
+    Failed(LuaError),
+}
+
+static SLOTS: SyncNonSync<RefCell<BTreeMap<i32, Slot>>> =
+    SyncNonSync(RefCell::new(BTreeMap::new()));
+static NEXT_ID: SyncNonSync<Cell<i32>> = SyncNonSync(Cell::new(0));
+
+/// spawn the future on the coroutine runtime and export the marker and the id,
+/// the result is delivered by [async_poll]
+pub(crate) fn start<O: Exportable + 'static>(fut: impl Future<Output = LuaResult<O>> + 'static) {
+    let id = NEXT_ID.get();
+    NEXT_ID.set(id.wrapping_add(1));
+    SLOTS.borrow_mut().insert(id, Slot::Pending);
+    spawn(async move {
+        let slot = match fut.await {
+            Ok(o) => Slot::Ready(Box::new(o)),
+            Err(e) => Slot::Failed(e),
+        };
+        // dropped by `async_drop` while running
+        if let Some(pending) = SLOTS.borrow_mut().get_mut(&id) {
+            *pending = slot;
+        }
+    });
+    (ASYNC_MARKER, id).export();
+}
+
+/// returns `"pending"`, `"ready", ...` or `"failed", msg`
+#[no_mangle]
+pub extern "C" fn async_poll() {
+    if super::panic::report_poisoned() {
+        return;
+    }
+    let id = match i32::import() {
+        Ok(id) => id,
+        Err(e) => {
+            unsafe { failed() };
+            e.as_str().export();
+            return;
+        }
+    };
+    let mut slots = SLOTS.borrow_mut();
+    unsafe { success() };
+    match slots.remove(&id) {
+        Some(Slot::Pending) => {
+            slots.insert(id, Slot::Pending);
+            "pending".export();
+        }
+        Some(Slot::Ready(o)) => {
+            "ready".export();
+            o.export();
+        }
+        Some(Slot::Failed(e)) => ("failed", e.as_str()).export(),
+        None => ("failed", "no such async call").export(),
+    }
+}
+
+/// `async_drop(id)`, discards the result of an async call which will not be polled anymore.
+///
+/// a result stays until it is polled or dropped, [ASYNC_SHIM] drops it if the waiting
+/// coroutine is terminated
+#[no_mangle]
+pub extern "C" fn async_drop() {
+    match i32::import() {
+        Ok(id) => {
+            let slot = SLOTS.borrow_mut().remove(&id);
+            drop(slot);
+            unsafe { success() };
+        }
+        Err(e) => {
+            unsafe { failed() };
+            e.as_str().export();
+        }
+    }
+}
+
+/// returns [ASYNC_SHIM]
+#[no_mangle]
+pub extern "C" fn async_shim() {
+    unsafe { success() };
+    ASYNC_SHIM.export();
+}
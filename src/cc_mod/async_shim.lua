@@ -0,0 +1,29 @@
+-- wraps a loaded wasm module, so async exported functions block the calling
+-- lua coroutine until the rust future finishes.
+-- usage: `local mylib = load(lib.async_shim())(lib)`
+local lib = ...
+local wrapped = {}
+for name, func in pairs(lib) do
+    wrapped[name] = function(...)
+        local r = table.pack(func(...))
+        if r[1] ~= "__cc_wasm_async" then
+            return table.unpack(r, 1, r.n)
+        end
+        local id = r[2]
+        while true do
+            local p = table.pack(lib.async_poll(id))
+            if p[1] == "ready" then
+                return table.unpack(p, 2, p.n)
+            elseif p[1] == "failed" then
+                error(p[2], 2)
+            end
+            ---@diagnostic disable-next-line: undefined-global
+            local ok, err = pcall(sleep, 0)
+            if not ok then
+                lib.async_drop(id)
+                error(err, 0)
+            end
+        end
+    end
+end
+return wrapped
@@ -0,0 +1,146 @@
+use std::cell::RefCell;
+
+use crate::{
+    lua_api::{
+        failed, next_import_type, success, Exportable, Importable, LuaError, LuaResult, Typed,
+    },
+    utils::{Number, SyncNonSync},
+};
+
+/// the host ABI version this crate is written against
+pub const HOST_VERSION: i32 = 4;
+/// the oldest host ABI version this crate can run on, the same as [HOST_VERSION].
+///
+/// the imports of every [Capability] are linked unconditionally, so an older host
+/// can not instantiate the module unless it provides them, for example as stubs which trap.
+/// such a host must not report a version, or `export_func` fails with
+/// "the cc wasm mod is too old"
+pub const MIN_HOST_VERSION: i32 = HOST_VERSION;
+
+/// host features which the crate checks before using them, see [has_capability].
+///
+/// every host reporting [MIN_HOST_VERSION] or newer has all of them, they matter for hosts
+/// which provide stub imports and do not negotiate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Capability {
+    /// `import_table_*` and `export_table_*`
+    Tables,
+    /// `import_ref`, `export_handle` and `release_handle`
+    Handles,
+    /// `import_savepoint`, `import_rollback` and `import_release_savepoint`
+    Savepoints,
+    /// `begin_call_args`, `call_method` and `call_function`
+    Calls,
+    /// `import_number_count`, `import_*_slice` and `export_*_slice`, used for numeric slices
+    BulkNumbers,
+    /// `call_eval_with`, used by [eval_with](crate::eval::eval_with)
+    EvalArgs,
+    /// `last_eval_id`, `eval_ready_id`, `import_from_eval_id` and `clear_eval_id`,
+    /// so several evals can be pending at once.
+    ///
+    /// clearing an id which is still running discards its result when it finishes
+    EvalIds,
+}
+impl Capability {
+    /// the name the host reports it with
+    pub const fn name(self) -> &'static str {
+        match self {
+            Capability::Tables => "tables",
+            Capability::Handles => "handles",
+            Capability::Savepoints => "savepoints",
+            Capability::Calls => "calls",
+            Capability::BulkNumbers => "bulk_numbers",
+            Capability::EvalArgs => "eval_args",
+            Capability::EvalIds => "eval_ids",
+        }
+    }
+    /// the host ABI version which has it
+    pub const fn since(self) -> i32 {
+        match self {
+            Capability::Tables
+            | Capability::Handles
+            | Capability::Savepoints
+            | Capability::Calls => 2,
+            Capability::BulkNumbers | Capability::EvalArgs => 3,
+            Capability::EvalIds => 4,
+        }
+    }
+}
+
+/// what the host reported when calling `export_func`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostInfo {
+    pub version: i32,
+    /// capabilities reported in addition to the ones of `version`
+    pub capabilities: Vec<String>,
+}
+impl HostInfo {
+    pub fn has(&self, capability: Capability) -> bool {
+        self.version >= capability.since()
+            || self.capabilities.iter().any(|c| c == capability.name())
+    }
+}
+
+static HOST: SyncNonSync<RefCell<Option<HostInfo>>> = SyncNonSync(RefCell::new(None));
+
+/// reads `export_func(version, capability...)` and fails the call if the host is too old.
+///
+/// a host which passes nothing did not negotiate, it is treated as having no capability
+/// by both [has_capability] and [require], and the crate falls back to plain evals where it can.
+/// called by the `export_func` generated by [export_funcs](crate::export_funcs)
+pub fn negotiate() -> bool {
+    if next_import_type() == Typed::None {
+        return true;
+    }
+    let Some(version) = Number::import().ok().and_then(Number::checked_i32) else {
+        return true;
+    };
+    let capabilities = Vec::<String>::import().unwrap_or_default();
+    HOST.replace(Some(HostInfo {
+        version,
+        capabilities,
+    }));
+    if version < MIN_HOST_VERSION {
+        unsafe { failed() };
+        format!("the cc wasm mod is too old, need host ABI >= {MIN_HOST_VERSION}, got {version}")
+            .export();
+        return false;
+    }
+    true
+}
+
+/// `None` if the host did not report its version
+pub fn host_info() -> Option<HostInfo> {
+    HOST.borrow().clone()
+}
+
+/// `false` if the host does not have it, or did not report its version.
+/// used by features which fall back to other host calls
+pub fn has_capability(capability: Capability) -> bool {
+    HOST.borrow().as_ref().is_some_and(|h| h.has(capability))
+}
+
+/// like [has_capability], but fails with an error naming the missing capability,
+/// for features which can not work without it
+pub fn require(capability: Capability) -> LuaResult<()> {
+    if has_capability(capability) {
+        return Ok(());
+    }
+    let version = HOST.borrow().as_ref().map_or_else(
+        || "an unknown version".to_owned(),
+        |h| h.version.to_string(),
+    );
+    Err(LuaError::from_string(format!(
+        "the cc wasm mod does not support {}, need host ABI >= {}, got {version}",
+        capability.name(),
+        capability.since(),
+    )))
+}
+
+/// returns [HOST_VERSION] and the crate version, for the host to check the module
+#[no_mangle]
+pub extern "C" fn abi_version() {
+    unsafe { success() };
+    (HOST_VERSION, env!("CARGO_PKG_VERSION")).export();
+}
@@ -0,0 +1,264 @@
+use std::{
+    any::Any,
+    cell::{Cell, Ref, RefCell, RefMut},
+    collections::BTreeMap,
+    rc::Rc,
+};
+
+use crate::{
+    lua_api::{
+        export_table, failed, success, value::LuaValue, Exportable, Importable, LuaError, LuaResult,
+    },
+    utils::SyncNonSync,
+};
+
+/// the key of the object id in the table exported for an object
+const OBJECT_KEY: &str = "__cc_wasm_object";
+/// lua source of the shim which turns exported objects into lua objects with methods
+pub const OBJECT_SHIM: &str = include_str!("object_shim.lua");
+
+/// a rust type whose methods can be called from lua with `obj:method(...)`,
+/// use `#[cc_export]` on its `impl` block to implement it.
+///
+/// ```ignore
+/// struct Counter(i32);
+/// impl LuaClass for Counter {
+///     fn class_name() -> &'static str {
+///         "Counter"
+///     }
+///     fn methods() -> &'static [&'static str] {
+///         &["add"]
+///     }
+///     unsafe fn call_method(&mut self, method: &str) -> bool {
+///         match method {
+///             "add" => {
+///                 let this = RefCell::new(self);
+///                 ExportFunc::call(&|n: i32| this.borrow_mut().0 += n, &ExportInfo::new("add"));
+///             }
+///             _ => return false,
+///         }
+///         true
+///     }
+/// }
+/// ```
+pub trait LuaClass: 'static {
+    /// the name shown by lua's `tostring`
+    fn class_name() -> &'static str
+    where
+        Self: Sized;
+    /// the methods callable from lua
+    fn methods() -> &'static [&'static str]
+    where
+        Self: Sized;
+    /// imports the arguments, calls the method and exports the result like [ExportFunc::call](super::ExportFunc::call).
+    ///
+    /// returns false if there is no such method
+    /// # Safety
+    /// should only be called by the exported `object_call`
+    unsafe fn call_method(&mut self, method: &str) -> bool;
+}
+
+/// a rust object shared with lua.
+///
+/// every time it is exported lua gets a new handle, the object is dropped when
+/// rust no longer holds it and lua released all the handles with `obj:drop()`.
+/// CC's lua never runs `__gc`, so a handle which is not dropped keeps the object alive.
+/// a method named `drop` can not be called from lua.
+///
+/// wrap the module with [OBJECT_SHIM] on the lua side to call its methods
+#[derive(Debug)]
+pub struct Object<T: ?Sized>(Rc<RefCell<T>>);
+impl<T: LuaClass> Object<T> {
+    pub fn new(value: T) -> Self {
+        Self(Rc::new(RefCell::new(value)))
+    }
+}
+impl<T: ?Sized> Object<T> {
+    /// # Panics
+    /// if the object is mutably borrowed, which includes while lua calls one of its methods
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.0.borrow()
+    }
+    /// # Panics
+    /// if the object is borrowed, which includes while lua calls one of its methods
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.0.borrow_mut()
+    }
+}
+impl<T: ?Sized> Clone for Object<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[derive(Clone)]
+struct Entry {
+    class_name: &'static str,
+    any: Rc<dyn Any>,
+    class: Rc<RefCell<dyn LuaClass>>,
+    /// the lua handles which are not dropped yet
+    handles: usize,
+}
+
+static OBJECTS: SyncNonSync<RefCell<BTreeMap<i32, Entry>>> =
+    SyncNonSync(RefCell::new(BTreeMap::new()));
+/// the id of every exported object by its address, so exporting it again reuses the entry
+static IDS: SyncNonSync<RefCell<BTreeMap<usize, i32>>> = SyncNonSync(RefCell::new(BTreeMap::new()));
+static NEXT_ID: SyncNonSync<Cell<i32>> = SyncNonSync(Cell::new(0));
+
+/// exported as `{ __cc_wasm_object = id, class = name, methods = { ... } }`
+impl<T: LuaClass> Exportable for Object<T> {
+    fn export(&self) {
+        // stays the same while the entry keeps the object alive
+        let address = Rc::as_ptr(&self.0) as *const () as usize;
+        let mut objects = OBJECTS.borrow_mut();
+        let existing = IDS.borrow().get(&address).copied();
+        let id = match existing.and_then(|id| Some((id, objects.get_mut(&id)?))) {
+            Some((id, entry)) => {
+                entry.handles += 1;
+                id
+            }
+            None => {
+                let id = NEXT_ID.get();
+                NEXT_ID.set(id.wrapping_add(1));
+                objects.insert(
+                    id,
+                    Entry {
+                        class_name: T::class_name(),
+                        any: self.0.clone(),
+                        class: self.0.clone(),
+                        handles: 1,
+                    },
+                );
+                IDS.borrow_mut().insert(address, id);
+                id
+            }
+        };
+        drop(objects);
+        export_table(|| {
+            OBJECT_KEY.export();
+            id.export();
+            "class".export();
+            T::class_name().export();
+            "methods".export();
+            export_table(|| {
+                for (i, method) in T::methods().iter().enumerate() {
+                    (i as i32 + 1).export();
+                    method.export();
+                }
+            });
+        });
+    }
+    fn lua_export_type() -> String {
+        T::class_name().to_owned()
+    }
+}
+/// imports an object previously exported to lua
+impl<T: LuaClass> Importable for Object<T> {
+    fn import() -> LuaResult<Self> {
+        let value = LuaValue::import()?;
+        let id = value
+            .get(OBJECT_KEY)
+            .and_then(LuaValue::as_integer)
+            .ok_or_else(|| {
+                LuaError::from_string(format!(
+                    "expect {}, got {}",
+                    T::class_name(),
+                    value.type_name()
+                ))
+            })?;
+        let objects = OBJECTS.borrow();
+        let entry = i32::try_from(id)
+            .ok()
+            .and_then(|id| objects.get(&id))
+            .ok_or_else(|| LuaError::from_str("object is dropped"))?;
+        let object = entry.any.clone().downcast::<RefCell<T>>().map_err(|_| {
+            LuaError::from_string(format!(
+                "expect {}, got {}",
+                T::class_name(),
+                entry.class_name
+            ))
+        })?;
+        Ok(Self(object))
+    }
+    fn lua_import_type() -> String {
+        T::class_name().to_owned()
+    }
+}
+
+fn import_call() -> LuaResult<(Entry, String)> {
+    let (id, method) = <(i32, String)>::import()?;
+    let entry = OBJECTS
+        .borrow()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| LuaError::from_str("object is dropped"))?;
+    Ok((entry, method))
+}
+
+/// `object_call(id, method, ...)`, calls a method of an exported object
+#[no_mangle]
+pub extern "C" fn object_call() {
+    if super::panic::report_poisoned() {
+        return;
+    }
+    let result = import_call().and_then(
+        |(
+            Entry {
+                class_name, class, ..
+            },
+            method,
+        )| {
+            let mut object = class
+                .try_borrow_mut()
+                .map_err(|_| LuaError::from_string(format!("{class_name} is borrowed by rust")))?;
+            if unsafe { object.call_method(&method) } {
+                Ok(())
+            } else {
+                Err(LuaError::from_string(format!(
+                    "no method '{method}' in {class_name}"
+                )))
+            }
+        },
+    );
+    if let Err(e) = result {
+        unsafe { failed() };
+        e.as_str().export();
+    }
+}
+
+/// `object_drop(id)`, releases one lua handle, called by `obj:drop()`
+#[no_mangle]
+pub extern "C" fn object_drop() {
+    match i32::import() {
+        Ok(id) => {
+            let mut objects = OBJECTS.borrow_mut();
+            let entry = match objects.get_mut(&id) {
+                Some(entry) if entry.handles > 1 => {
+                    entry.handles -= 1;
+                    None
+                }
+                Some(_) => objects.remove(&id),
+                None => None,
+            };
+            drop(objects);
+            if entry.is_some() {
+                IDS.borrow_mut().retain(|_, entry_id| *entry_id != id);
+            }
+            // dropped after releasing `OBJECTS`, in case the object exports or drops objects itself
+            drop(entry);
+            unsafe { success() };
+        }
+        Err(e) => {
+            unsafe { failed() };
+            e.as_str().export();
+        }
+    }
+}
+
+/// returns [OBJECT_SHIM]
+#[no_mangle]
+pub extern "C" fn object_shim() {
+    unsafe { success() };
+    OBJECT_SHIM.export();
+}
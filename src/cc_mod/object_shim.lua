@@ -0,0 +1,55 @@
+-- wraps a loaded wasm module, so rust objects returned by exported functions
+-- become lua objects, whose methods are called with `obj:method(...)`.
+-- usage: `local mylib = load(lib.object_shim())(lib)`
+-- call `obj:drop()` when an object is no longer used, otherwise it is never freed.
+local lib = ...
+local wrap_all
+
+local function wrap(value)
+    if type(value) ~= "table" or value.__cc_wasm_object == nil then
+        return value
+    end
+    local id = value.__cc_wasm_object
+    local dropped = false
+    local methods = {}
+    for _, name in ipairs(value.methods) do
+        methods[name] = function(_, ...)
+            if dropped then
+                error(value.class .. " is dropped", 2)
+            end
+            return wrap_all(lib.object_call(id, name, ...))
+        end
+    end
+    -- CC's lua never calls `__gc`, so handles have to be released explicitly
+    methods.drop = function()
+        if not dropped then
+            dropped = true
+            lib.object_drop(id)
+        end
+    end
+    -- the id is kept in the object, so it can be passed back to rust
+    return setmetatable({ __cc_wasm_object = id }, {
+        __index = methods,
+        __name = value.class,
+        __tostring = function()
+            return value.class .. ": " .. id
+        end,
+        __gc = methods.drop,
+    })
+end
+
+wrap_all = function(...)
+    local r = table.pack(...)
+    for i = 1, r.n do
+        r[i] = wrap(r[i])
+    end
+    return table.unpack(r, 1, r.n)
+end
+
+local wrapped = {}
+for name, func in pairs(lib) do
+    wrapped[name] = function(...)
+        return wrap_all(func(...))
+    end
+end
+return wrapped
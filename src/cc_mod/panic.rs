@@ -0,0 +1,88 @@
+use std::cell::{Cell, RefCell};
+
+use crate::{
+    lua_api::{debug::show_str, failed, success, Exportable},
+    utils::SyncNonSync,
+};
+
+/// the report of the first panic
+static POISONED: SyncNonSync<RefCell<Option<String>>> = SyncNonSync(RefCell::new(None));
+static INSTALLED: SyncNonSync<Cell<bool>> = SyncNonSync(Cell::new(false));
+
+/// installs the panic hook, called by `export_func`.
+///
+/// the hook reports `panicked at file:line:col: message` with `show_str` and as the error of the
+/// current call, then marks the runtime as poisoned.
+/// the previous hook still runs after it
+pub fn install_hook() {
+    if INSTALLED.replace(true) {
+        return;
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = match (
+            payload.downcast_ref::<&str>(),
+            payload.downcast_ref::<String>(),
+        ) {
+            (Some(s), _) => s,
+            (_, Some(s)) => s.as_str(),
+            _ => "Box<dyn Any>",
+        };
+        let report = match info.location() {
+            Some(l) => format!(
+                "panicked at {}:{}:{}: {message}",
+                l.file(),
+                l.line(),
+                l.column()
+            ),
+            None => format!("panicked: {message}"),
+        };
+        show_str(&report);
+        if let Ok(mut poisoned) = POISONED.try_borrow_mut() {
+            poisoned.get_or_insert_with(|| report.clone());
+        }
+        unsafe { failed() };
+        report.export();
+        previous(info);
+    }));
+}
+
+/// the report of the first panic, if any.
+///
+/// the runtime should not be used after a panic, its state may be broken
+pub fn panic_message() -> Option<String> {
+    POISONED.try_borrow().ok()?.clone()
+}
+
+/// fails the current call with the panic report if the runtime is poisoned,
+/// used at the start of exported functions
+#[doc(hidden)]
+pub fn report_poisoned() -> bool {
+    match panic_message() {
+        Some(report) => {
+            unsafe { failed() };
+            report.export();
+            true
+        }
+        None => false,
+    }
+}
+
+/// returns `false`, or `true, report` after a panic.
+///
+/// ```lua
+/// local ok, err = pcall(lib.run)
+/// if not ok and lib.poisoned() then
+///     printError(select(2, lib.poisoned()))
+///     return
+/// end
+/// ```
+#[no_mangle]
+pub extern "C" fn poisoned() {
+    unsafe { success() };
+    match panic_message() {
+        Some(report) => (true, report).export(),
+        None => false.export(),
+    }
+}
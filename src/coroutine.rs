@@ -1,3 +1,5 @@
+#[cfg(not(target_os = "unknown"))]
+use std::time::{Duration, Instant};
 use std::{
     cell::{Cell, RefCell},
     collections::VecDeque,
@@ -7,7 +9,6 @@ use std::{
     pin::Pin,
     ptr,
     task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
-    time::{Duration, Instant},
 };
 
 use fut_blocker::Stopper;
@@ -110,7 +111,6 @@ mod tick_sync {
         fmt::Debug,
         future::Future,
         task::Poll,
-        time::Duration,
     };
 
     use crate::{
@@ -119,6 +119,7 @@ mod tick_sync {
         utils::SyncNonSync,
     };
 
+    #[cfg(not(target_os = "unknown"))]
     use super::sleep;
     /// limit the corountine's loop to run exactly once every tick.
     ///
@@ -139,7 +140,7 @@ mod tick_sync {
     ///         let mut ts = TickSyncer::new();
     ///         // if not disable sync will cause other coroutines to wait for this sleep to finish
     ///         let no_sync = ts.no_sync();
-    ///         sleep(Duration::from_sec(5)).await;
+    ///         sleep(std::time::Duration::from_secs(5)).await;
     ///         drop(no_sync);
     ///         loop{
     ///             // code here will run once every tick
@@ -149,7 +150,7 @@ mod tick_sync {
     ///
     ///     async {
     ///         let mut ts = TickSyncer::new();
-    ///         ts.sleep(Duration::from_sec(5)).await;
+    ///         ts.sleep(std::time::Duration::from_secs(5)).await;
     ///         loop{
     ///             // code here will run once every tick
     ///             ts.sync().await;
@@ -181,7 +182,9 @@ mod tick_sync {
             desubscribe();
             NoSync
         }
-        pub async fn sleep(&mut self, dur: Duration) {
+        #[cfg(not(target_os = "unknown"))]
+        #[cfg_attr(docsrs, doc(cfg(not(target_os = "unknown"))))]
+        pub async fn sleep(&mut self, dur: std::time::Duration) {
             desubscribe();
             sleep(dur).await;
             subscribe();
@@ -444,6 +447,10 @@ pub fn yield_counter() -> usize {
 pub extern "C" fn tick() {
     use stop::stop;
 
+    if crate::cc_mod::panic::report_poisoned() {
+        return;
+    }
+
     COROUTINES
         .borrow_mut()
         .append(SPAWNED.borrow_mut().as_mut());
@@ -467,6 +474,10 @@ pub extern "C" fn tick() {
 extern "C" fn tick() {
     use stop::stop;
 
+    if crate::cc_mod::panic::report_poisoned() {
+        return;
+    }
+
     COROUTINES
         .borrow_mut()
         .append(SPAWNED.borrow_mut().as_mut());
@@ -1,6 +1,20 @@
-use std::{future::Future, marker::PhantomData, task::Poll};
+use std::{cell::Cell, future::Future, marker::PhantomData, task::Poll};
 
-use crate::lua_api::{Importable, LuaResult};
+use crate::{
+    cc_mod::host::{has_capability, require, Capability},
+    lua_api::{lua_ref::LuaRef, Exportable, Importable, LuaResult},
+    utils::SyncNonSync,
+};
+
+#[cfg(feature = "coroutine")]
+#[cfg_attr(docsrs, doc(cfg(feature = "coroutine")))]
+pub mod batch;
+pub mod chunk;
+pub mod env;
+pub mod globals;
+mod traced;
+
+pub use traced::{eval_traced, eval_traced_named, exec_traced, EvalError, DEFAULT_CHUNK_NAME};
 
 mod ffi {
     #[cfg(target_arch = "wasm32")]
@@ -9,9 +23,19 @@ mod ffi {
     extern "C" {
 
         pub fn call_eval(addr: i32, len: i32) -> i32;
+        pub fn call_eval_with(addr: i32, len: i32) -> i32;
         pub fn eval_ready() -> i32;
         pub fn clear_eval();
         pub fn import_from_eval();
+
+        pub fn last_eval_id() -> i32;
+        pub fn eval_ready_id(id: i32) -> i32;
+        pub fn import_from_eval_id(id: i32);
+        pub fn clear_eval_id(id: i32);
+
+        pub fn begin_call_args();
+        pub fn call_method(id: i32, addr: i32, len: i32, pass_self: i32) -> i32;
+        pub fn call_function(id: i32) -> i32;
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -22,6 +46,9 @@ mod ffi {
         pub unsafe fn call_eval(addr: i32, len: i32) -> i32 {
             todo!()
         }
+        pub unsafe fn call_eval_with(addr: i32, len: i32) -> i32 {
+            todo!()
+        }
         pub unsafe fn eval_ready() -> i32 {
             todo!()
         }
@@ -31,6 +58,29 @@ mod ffi {
         pub unsafe fn import_from_eval() {
             todo!()
         }
+
+        pub unsafe fn last_eval_id() -> i32 {
+            todo!()
+        }
+        pub unsafe fn eval_ready_id(id: i32) -> i32 {
+            todo!()
+        }
+        pub unsafe fn import_from_eval_id(id: i32) {
+            todo!()
+        }
+        pub unsafe fn clear_eval_id(id: i32) {
+            todo!()
+        }
+
+        pub unsafe fn begin_call_args() {
+            todo!()
+        }
+        pub unsafe fn call_method(id: i32, addr: i32, len: i32, pass_self: i32) -> i32 {
+            todo!()
+        }
+        pub unsafe fn call_function(id: i32) -> i32 {
+            todo!()
+        }
     }
 }
 
@@ -42,12 +92,157 @@ fn eval_ready() -> bool {
     let a = unsafe { ffi::eval_ready() };
     a != 0
 }
+fn eval_ready_id(id: i32) -> bool {
+    let a = unsafe { ffi::eval_ready_id(id) };
+    a != 0
+}
+
+/// set when an [Eval] using [Slot::Global] is dropped before its result is ready
+static ABANDONED: SyncNonSync<Cell<bool>> = SyncNonSync(Cell::new(false));
+
+/// where the host puts the result of a sent request
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    /// the single slot of hosts without [Capability::EvalIds], the host is busy until it is cleared
+    Global,
+    /// the slot of the request with this id
+    Id(i32),
+}
+impl Slot {
+    /// the slot of the request just sent
+    fn of_sent() -> Self {
+        if has_capability(Capability::EvalIds) {
+            Slot::Id(unsafe { ffi::last_eval_id() })
+        } else {
+            Slot::Global
+        }
+    }
+    fn ready(self) -> bool {
+        match self {
+            Slot::Global => eval_ready(),
+            Slot::Id(id) => eval_ready_id(id),
+        }
+    }
+    fn import<O>(self, import: impl FnOnce() -> LuaResult<O>) -> LuaResult<O> {
+        match self {
+            Slot::Global => unsafe {
+                ffi::import_from_eval();
+                let o = import();
+                ffi::clear_eval();
+                o
+            },
+            Slot::Id(id) => unsafe {
+                ffi::import_from_eval_id(id);
+                let o = import();
+                ffi::clear_eval_id(id);
+                o
+            },
+        }
+    }
+    /// drops the result of a request nobody waits for anymore
+    fn discard(self) {
+        match self {
+            Slot::Global if eval_ready() => unsafe { ffi::clear_eval() },
+            // cleared by the next request once it is ready, the host is busy until then
+            Slot::Global => ABANDONED.set(true),
+            Slot::Id(id) => unsafe { ffi::clear_eval_id(id) },
+        }
+    }
+    /// clears the global slot if it holds the result of a dropped [Eval]
+    fn clear_abandoned() {
+        if ABANDONED.get() && eval_ready() {
+            unsafe { ffi::clear_eval() };
+            ABANDONED.set(false);
+        }
+    }
+}
 
-struct Eval<'a, O: Importable + Unpin> {
-    data: Option<&'a str>,
+/// what an [Eval] asks the host to run
+enum Request<'a, A> {
+    Script(&'a str),
+    /// a script which receives `args` as `...`
+    ScriptWith {
+        script: &'a str,
+        args: A,
+    },
+    /// `target[name](args)`, or `target:name(args)` if `pass_self`
+    Method {
+        target: &'a LuaRef,
+        name: &'a str,
+        pass_self: bool,
+        args: A,
+    },
+    /// `target(args)`
+    Call {
+        target: &'a LuaRef,
+        args: A,
+    },
+}
+impl<A: Exportable> Request<'_, A> {
+    /// the host capability needed to send it
+    fn capability(&self) -> Option<Capability> {
+        match self {
+            Request::Script(_) => None,
+            Request::ScriptWith { .. } => Some(Capability::EvalArgs),
+            Request::Method { .. } | Request::Call { .. } => Some(Capability::Calls),
+        }
+    }
+    /// returns `false` if the host is busy
+    fn send(&self) -> bool {
+        match self {
+            Request::Script(s) => call_eval(s),
+            Request::ScriptWith { script, args } => {
+                let a = unsafe {
+                    ffi::begin_call_args();
+                    args.export();
+                    ffi::call_eval_with(
+                        *script as *const str as *const () as usize as i32,
+                        script.len() as i32,
+                    )
+                };
+                a != 0
+            }
+            Request::Method {
+                target,
+                name,
+                pass_self,
+                args,
+            } => {
+                let a = unsafe {
+                    ffi::begin_call_args();
+                    args.export();
+                    ffi::call_method(
+                        target.id(),
+                        *name as *const str as *const () as usize as i32,
+                        name.len() as i32,
+                        *pass_self as i32,
+                    )
+                };
+                a != 0
+            }
+            Request::Call { target, args } => {
+                let a = unsafe {
+                    ffi::begin_call_args();
+                    args.export();
+                    ffi::call_function(target.id())
+                };
+                a != 0
+            }
+        }
+    }
+}
+
+struct Eval<'a, O: Unpin, A: Exportable + Unpin = (), I = fn() -> LuaResult<O>> {
+    request: Option<Request<'a, A>>,
+    /// set once the request is sent
+    slot: Option<Slot>,
+    /// imports the result, `O::import` unless the result is spread over several futures
+    import: Option<I>,
     out: PhantomData<O>,
 }
-impl<O: Importable + Unpin> Future for Eval<'_, O> {
+impl<O: Unpin, A: Exportable + Unpin, I: FnOnce() -> LuaResult<O> + Unpin> Future
+    for Eval<'_, O, A, I>
+{
     type Output = LuaResult<O>;
 
     fn poll(
@@ -56,44 +251,186 @@ impl<O: Importable + Unpin> Future for Eval<'_, O> {
     ) -> std::task::Poll<Self::Output> {
         let unpin = self.get_mut();
 
-        if let Some(v) = &unpin.data {
-            if call_eval(v) {
-                unpin.data = None;
+        if let Some(v) = &unpin.request {
+            if let Some(Err(e)) = v.capability().map(require) {
+                unpin.request = None;
+                return Poll::Ready(Err(e));
+            }
+            Slot::clear_abandoned();
+            if v.send() {
+                unpin.request = None;
+                unpin.slot = Some(Slot::of_sent());
             } else {
                 cx.waker().wake_by_ref();
                 return Poll::Pending;
             }
         }
 
-        if !eval_ready() {
+        let Some(slot) = unpin.slot else {
+            unreachable!("polled after completion")
+        };
+        if !slot.ready() {
             cx.waker().wake_by_ref();
             return Poll::Pending;
         }
-        unsafe {
-            ffi::import_from_eval();
-        }
-        let o = O::import();
-        unsafe {
-            ffi::clear_eval();
+        unpin.slot = None;
+        let import = unpin.import.take().expect("polled after completion");
+        Poll::Ready(slot.import(import))
+    }
+}
+/// dropping a sent [Eval] discards its result, so it is not read by the next one
+impl<O: Unpin, A: Exportable + Unpin, I> Drop for Eval<'_, O, A, I> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            slot.discard();
         }
-        Poll::Ready(o)
     }
 }
 
 /// run a lua script in the lua context
 pub fn eval<O: Importable + Unpin + 'static>(s: &str) -> impl '_ + Future<Output = LuaResult<O>> {
-    Eval {
-        data: Some(s),
+    Eval::<_, ()> {
+        request: Some(Request::Script(s)),
+        slot: None,
+        import: Some(O::import),
         out: PhantomData,
     }
 }
 /// run a lua script in the lua context and returns nothing
 pub fn exec(s: &str) -> impl '_ + Future<Output = LuaResult<()>> {
+    eval(s)
+}
+/// run a lua script in the lua context, `args` are passed to the script as `...`.
+///
+/// unlike formatting the values into the script, any string is passed as it is
+/// ```no_run
+/// # use cc_wasm_api::{eval::eval_with, lua_api::LuaResult};
+/// # async fn f() -> LuaResult<()> {
+/// let name = "it's \"quoted\"";
+/// let len: i32 = eval_with("local s = ... return #s", name).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn eval_with<'a, O: Importable + Unpin + 'static, A: Exportable + Unpin + 'a>(
+    s: &'a str,
+    args: A,
+) -> impl 'a + Future<Output = LuaResult<O>> {
     Eval {
-        data: Some(s),
+        request: Some(Request::ScriptWith { script: s, args }),
+        slot: None,
+        import: Some(O::import),
         out: PhantomData,
     }
 }
+/// like [eval_with], but returns nothing
+pub fn exec_with<'a, A: Exportable + Unpin + 'a>(
+    s: &'a str,
+    args: A,
+) -> impl 'a + Future<Output = LuaResult<()>> {
+    eval_with(s, args)
+}
+/// like [eval_with], but the result is imported by `import`
+pub(crate) fn eval_with_import<
+    'a,
+    O: Unpin + 'static,
+    A: Exportable + Unpin + 'a,
+    I: FnOnce() -> LuaResult<O> + Unpin + 'a,
+>(
+    s: &'a str,
+    args: A,
+    import: I,
+) -> impl 'a + Future<Output = LuaResult<O>> {
+    Eval {
+        request: Some(Request::ScriptWith { script: s, args }),
+        slot: None,
+        import: Some(import),
+        out: PhantomData,
+    }
+}
+/// call `target[name](args)`, like calling a method of a peripheral
+pub(crate) fn call_method<'a, O: Importable + Unpin + 'static, A: Exportable + Unpin + 'a>(
+    target: &'a LuaRef,
+    name: &'a str,
+    pass_self: bool,
+    args: A,
+) -> impl 'a + Future<Output = LuaResult<O>> {
+    Eval {
+        request: Some(Request::Method {
+            target,
+            name,
+            pass_self,
+            args,
+        }),
+        slot: None,
+        import: Some(O::import),
+        out: PhantomData,
+    }
+}
+/// call `target(args)`
+pub(crate) fn call_function<'a, O: Importable + Unpin + 'static, A: Exportable + Unpin + 'a>(
+    target: &'a LuaRef,
+    args: A,
+) -> impl 'a + Future<Output = LuaResult<O>> {
+    Eval {
+        request: Some(Request::Call { target, args }),
+        slot: None,
+        import: Some(O::import),
+        out: PhantomData,
+    }
+}
+/// like [eval], but fails once `timeout` has passed without a result, timed like
+/// [sleep](crate::coroutine::sleep).
+///
+/// the script is not stopped, its result is discarded when it finishes
+#[cfg(all(feature = "coroutine", not(target_os = "unknown")))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "coroutine", not(target_os = "unknown"))))
+)]
+pub fn eval_timeout<O: Importable + Unpin + 'static>(
+    s: &str,
+    timeout: std::time::Duration,
+) -> impl '_ + Future<Output = LuaResult<O>> {
+    let mut eval = Some(Eval::<O, ()> {
+        request: Some(Request::Script(s)),
+        slot: None,
+        import: Some(O::import),
+        out: PhantomData,
+    });
+    async move {
+        let mut timer = std::pin::pin!(crate::coroutine::sleep(timeout));
+        std::future::poll_fn(|cx| {
+            let Some(pending) = &mut eval else {
+                unreachable!("polled after completion")
+            };
+            if let Poll::Ready(o) = std::pin::Pin::new(pending).poll(cx) {
+                eval = None;
+                return Poll::Ready(o);
+            }
+            if timer.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            // dropped now rather than with this future, so the next eval can run
+            eval = None;
+            Poll::Ready(Err(crate::lua_api::LuaError::from_string(format!(
+                "eval timed out after {timeout:?}"
+            ))))
+        })
+        .await
+    }
+}
+/// like [eval_timeout], but returns nothing
+#[cfg(all(feature = "coroutine", not(target_os = "unknown")))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "coroutine", not(target_os = "unknown"))))
+)]
+pub fn exec_timeout(
+    s: &str,
+    timeout: std::time::Duration,
+) -> impl '_ + Future<Output = LuaResult<()>> {
+    eval_timeout(s, timeout)
+}
 /// yield from the lua loop.
 pub async fn yield_lua() {
     use crate::lua_api::LuaResult;
@@ -0,0 +1,16 @@
+-- runs every script passed in `...`, returns `ok, count, results...` for each of them
+local scripts = table.pack(...)
+local out, len = {}, 0
+for i = 1, scripts.n do
+    local f, err = load(scripts[i], "=batch", "t", _ENV)
+    local r = f and table.pack(pcall(f)) or { n = 2, false, err }
+    if not r[1] then
+        r[2] = tostring(r[2])
+    end
+    out[len + 1], out[len + 2] = r[1], r.n - 1
+    for j = 2, r.n do
+        out[len + j + 1] = r[j]
+    end
+    len = len + r.n + 1
+end
+return table.unpack(out, 1, len)
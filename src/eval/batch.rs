@@ -0,0 +1,142 @@
+use std::{
+    cell::{Cell, RefCell},
+    future::{poll_fn, Future},
+    rc::Rc,
+    task::Poll,
+};
+
+use crate::{
+    cc_mod::host::{has_capability, Capability},
+    coroutine::spawn,
+    lua_api::{abort_next_import, ImportSavepoint, Importable, LuaError, LuaResult},
+    utils::{Number, SyncNonSync},
+};
+
+use super::eval_with_import;
+
+/// lua source of the chunk which runs a batch
+const BATCH_SCRIPT: &str = include_str!("batch.lua");
+
+/// the result of a queued script, filled by the dispatcher
+type ResultCell<O> = Rc<RefCell<Option<LuaResult<O>>>>;
+
+trait Pending {
+    /// imports `ok, count, results...` of the script, all of them are consumed even if
+    /// its results fail to import. `false` if `ok, count` failed, then the
+    /// values of the later scripts can not be found
+    fn import(&self) -> bool;
+    /// the result can not be imported, or the whole batch failed
+    fn fail(&self, e: LuaError);
+}
+impl<O: Importable> Pending for ResultCell<O> {
+    fn import(&self) -> bool {
+        let header = (|| {
+            let ok = bool::import()?;
+            let count = Number::import()?
+                .checked_i64()
+                .filter(|count| *count >= 0)
+                .ok_or_else(|| LuaError::from_str("invalid result count"))?;
+            Ok((ok, count))
+        })();
+        let (ok, count) = match header {
+            Ok(header) => header,
+            Err(e) => {
+                self.fail(e);
+                return false;
+            }
+        };
+        // `O` may read fewer or more values than the script returned, skip exactly `count`
+        let savepoint = ImportSavepoint::new();
+        let result = if ok {
+            O::import()
+        } else {
+            String::import().and_then(|e| Err(LuaError::from_string(e)))
+        };
+        savepoint.rollback();
+        for _ in 0..count {
+            unsafe { abort_next_import() };
+        }
+        self.replace(Some(result));
+        true
+    }
+    fn fail(&self, e: LuaError) {
+        self.replace(Some(Err(e)));
+    }
+}
+
+struct Queued {
+    script: String,
+    pending: Box<dyn Pending>,
+}
+
+static QUEUE: SyncNonSync<RefCell<Vec<Queued>>> = SyncNonSync(RefCell::new(Vec::new()));
+static SCHEDULED: SyncNonSync<Cell<bool>> = SyncNonSync(Cell::new(false));
+
+/// sends every script queued since the last dispatch in one eval
+async fn dispatch() {
+    SCHEDULED.set(false);
+    let (scripts, pending): (Vec<String>, Vec<Box<dyn Pending>>) = QUEUE
+        .take()
+        .into_iter()
+        .map(|q| (q.script, q.pending))
+        .unzip();
+    let result = eval_with_import(BATCH_SCRIPT, scripts.as_slice(), || {
+        let mut pending = pending.iter();
+        if pending.by_ref().all(|p| p.import()) {
+            return Ok(());
+        }
+        let e = LuaError::from_str("an earlier result of the batch failed to import");
+        pending.for_each(|p| p.fail(e.clone()));
+        Ok(())
+    })
+    .await;
+    if let Err(e) = result {
+        pending.iter().for_each(|p| p.fail(e.clone()));
+    }
+}
+
+/// run a lua script in the next batch, and import its return values as `O`.
+///
+/// the script is queued when this is called, so it runs even if the future is dropped.
+/// all the scripts queued within a tick are sent in one host call on the next tick,
+/// each of them runs in its own `pcall` and the future gets only its own result or error.
+///
+/// on hosts without [Savepoints](Capability::Savepoints) and [EvalArgs](Capability::EvalArgs)
+/// the script runs on its own like [eval](super::eval)
+pub fn eval<O: Importable + Unpin + 'static>(
+    script: impl Into<String>,
+) -> impl Future<Output = LuaResult<O>> {
+    let script = script.into();
+    let queued = if has_capability(Capability::Savepoints) && has_capability(Capability::EvalArgs) {
+        let cell: ResultCell<O> = Rc::new(RefCell::new(None));
+        QUEUE.borrow_mut().push(Queued {
+            script,
+            pending: Box::new(cell.clone()),
+        });
+        if !SCHEDULED.replace(true) {
+            spawn(dispatch());
+        }
+        Ok(cell)
+    } else {
+        Err(script)
+    };
+    async move {
+        match queued {
+            Ok(cell) => {
+                poll_fn(|cx| match cell.take() {
+                    Some(result) => Poll::Ready(result),
+                    None => {
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                })
+                .await
+            }
+            Err(script) => super::eval(&script).await,
+        }
+    }
+}
+/// like [eval], but returns nothing
+pub fn exec(script: impl Into<String>) -> impl Future<Output = LuaResult<()>> {
+    eval(script)
+}
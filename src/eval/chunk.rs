@@ -0,0 +1,81 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    future::Future,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    lua_api::{function::LuaFunction, Exportable, Importable, LuaResult},
+    utils::SyncNonSync,
+};
+
+use super::eval_with;
+
+/// loads the chunk in the `_ENV` of the compiling eval, so it sees the same globals
+const LOAD_SCRIPT: &str = r#"local src, name = ...
+return assert(load(src, name, "t", _ENV))"#;
+
+/// compiled chunks by `(name, source)`, an entry is dead once every [LuaChunk] of it is dropped
+type Cache = BTreeMap<(String, String), Weak<LuaFunction>>;
+static CHUNKS: SyncNonSync<RefCell<Cache>> = SyncNonSync(RefCell::new(BTreeMap::new()));
+
+/// a lua chunk compiled once by the host, calling it only sends the arguments.
+///
+/// compiling the same source again returns the same chunk while it is alive,
+/// the host releases the compiled function when the last clone is dropped.
+///
+/// the chunk keeps the `_ENV` of the eval which compiled it, which may not be shared with
+/// other evals, so globals it sets are only seen by later calls of the same chunk.
+/// use the host's `global` table to share state
+/// ```no_run
+/// # use cc_wasm_api::{eval::chunk::LuaChunk, lua_api::LuaResult};
+/// # async fn f() -> LuaResult<()> {
+/// let add = LuaChunk::compile("local a, b = ... return a + b").await?;
+/// for i in 0..10 {
+///     let sum: i32 = add.call((i, 1)).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LuaChunk(Rc<LuaFunction>);
+
+impl LuaChunk {
+    /// compile `src`, its arguments are `...`
+    pub async fn compile(src: &str) -> LuaResult<Self> {
+        Self::compile_named(src, "=chunk").await
+    }
+    /// compile `src`, `name` is the chunk name shown in errors, like the second argument of lua's `load`
+    pub async fn compile_named(src: &str, name: &str) -> LuaResult<Self> {
+        let key = (name.to_owned(), src.to_owned());
+        if let Some(function) = CHUNKS.borrow().get(&key).and_then(Weak::upgrade) {
+            return Ok(Self(function));
+        }
+        let function: LuaFunction = eval_with(LOAD_SCRIPT, (src, name)).await?;
+        let function = Rc::new(function);
+        let mut chunks = CHUNKS.borrow_mut();
+        chunks.retain(|_, f| f.strong_count() > 0);
+        chunks.insert(key, Rc::downgrade(&function));
+        Ok(Self(function))
+    }
+    /// run the chunk with `args` as `...`, and import its return values as `O`
+    pub fn call<'a, A: Exportable + Unpin + 'a, O: Importable + Unpin + 'static>(
+        &'a self,
+        args: A,
+    ) -> impl 'a + Future<Output = LuaResult<O>> {
+        self.0.call(args)
+    }
+    pub fn as_function(&self) -> &LuaFunction {
+        &self.0
+    }
+}
+/// exported as the compiled function
+impl Exportable for LuaChunk {
+    fn export(&self) {
+        self.0.export();
+    }
+    fn lua_export_type() -> String {
+        "function".to_owned()
+    }
+}
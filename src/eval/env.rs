@@ -0,0 +1,124 @@
+use std::future::Future;
+
+use crate::lua_api::{lua_ref::LuaRef, Exportable, Importable, LuaResult};
+
+use super::eval_with;
+
+/// creates the table, `...` is the table to inherit from, or nil
+const NEW_SCRIPT: &str = r#"local parent = ...
+return setmetatable({}, parent and { __index = parent })"#;
+/// the table scripts inherit from with [Inherit::Globals]
+const GLOBALS_SCRIPT: &str = "return _ENV";
+/// runs `src` in `env`, the rest of `...` are its arguments
+const RUN_SCRIPT: &str = r#"local src, name, env = ...
+local f, err = load(src, "=" .. name, "t", env)
+if not f then
+    error(err, 0)
+end
+return f(select(4, ...))"#;
+
+/// what a [LuaEnv] falls back to when a global is not set in it.
+///
+/// writing a global always sets it in the [LuaEnv] itself
+#[derive(Debug, Clone)]
+pub enum Inherit<'a> {
+    /// nothing, not even lua's standard library
+    Nothing,
+    /// the `_ENV` of the script which creates the environment, it sees what
+    /// [eval](super::eval) scripts see, but may not be shared with later evals
+    Globals,
+    /// another environment, which itself may inherit
+    Env(&'a LuaEnv),
+}
+
+/// a lua table which scripts can run in as their globals, see lua's `load`.
+///
+/// globals set by a script stay in the environment, so separate modules or subsystems
+/// can keep their state without touching each other's
+/// ```no_run
+/// # use cc_wasm_api::{eval::env::{Inherit, LuaEnv}, lua_api::LuaResult};
+/// # async fn f() -> LuaResult<()> {
+/// let env = LuaEnv::new(Inherit::Globals).await?;
+/// env.exec("counter = (counter or 0) + 1").await?;
+/// let counter: i32 = env.eval("return counter").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LuaEnv(LuaRef);
+
+impl LuaEnv {
+    pub async fn new(inherit: Inherit<'_>) -> LuaResult<Self> {
+        let parent = match inherit {
+            Inherit::Nothing => None,
+            Inherit::Globals => Some(super::eval::<LuaRef>(GLOBALS_SCRIPT).await?),
+            Inherit::Env(env) => Some(env.0.clone()),
+        };
+        Ok(Self(eval_with(NEW_SCRIPT, parent).await?))
+    }
+    /// a new environment inheriting from this one
+    pub async fn child(&self) -> LuaResult<Self> {
+        Self::new(Inherit::Env(self)).await
+    }
+    /// run a lua script in this environment
+    pub fn eval<'a, O: Importable + Unpin + 'static>(
+        &'a self,
+        script: &'a str,
+    ) -> impl 'a + Future<Output = LuaResult<O>> {
+        self.eval_with(script, ())
+    }
+    /// run a lua script in this environment and returns nothing
+    pub fn exec<'a>(&'a self, script: &'a str) -> impl 'a + Future<Output = LuaResult<()>> {
+        self.eval(script)
+    }
+    /// run a lua script in this environment, `args` are passed to the script as `...`
+    pub fn eval_with<'a, O: Importable + Unpin + 'static, A: Exportable + Unpin + 'a>(
+        &'a self,
+        script: &'a str,
+        args: A,
+    ) -> impl 'a + Future<Output = LuaResult<O>> {
+        eval_with(RUN_SCRIPT, (script, "env", self.0.clone(), args))
+    }
+    /// like [eval_with](Self::eval_with), but returns nothing
+    pub fn exec_with<'a, A: Exportable + Unpin + 'a>(
+        &'a self,
+        script: &'a str,
+        args: A,
+    ) -> impl 'a + Future<Output = LuaResult<()>> {
+        self.eval_with(script, args)
+    }
+    /// like [globals::get](super::globals::get), but reads the environment.
+    ///
+    /// inherited values are read too
+    pub async fn get<O: Importable + Unpin + 'static>(&self, path: &str) -> LuaResult<O> {
+        super::globals::access("get", Some(&self.0), path, ()).await
+    }
+    /// like [globals::set](super::globals::set), but sets the value in the environment
+    pub async fn set<A: Exportable + Unpin>(&self, path: &str, value: A) -> LuaResult<()> {
+        super::globals::access("set", Some(&self.0), path, value).await
+    }
+    /// like [globals::has](super::globals::has), but checks the environment
+    pub async fn has(&self, path: &str) -> LuaResult<bool> {
+        super::globals::access("has", Some(&self.0), path, ()).await
+    }
+    /// the environment table
+    pub fn as_lua_ref(&self) -> &LuaRef {
+        &self.0
+    }
+}
+impl Importable for LuaEnv {
+    fn import() -> LuaResult<Self> {
+        Ok(Self(LuaRef::import()?))
+    }
+    fn lua_import_type() -> String {
+        "table".to_owned()
+    }
+}
+impl Exportable for LuaEnv {
+    fn export(&self) {
+        self.0.export();
+    }
+    fn lua_export_type() -> String {
+        "table".to_owned()
+    }
+}
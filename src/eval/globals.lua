@@ -0,0 +1,37 @@
+-- `op, root, path, value`, gets, sets or checks the value at the dotted `path` of `root` or the globals
+local op, root, path, value = ...
+local keys = {}
+for key in string.gmatch(path, "[^.]+") do
+    keys[#keys + 1] = key
+end
+-- without a root, the first key is looked up in the host's persistent `global` table and
+-- then in `_ENV`, which may be a fresh table for every eval
+local persistent = not root and type(global) == "table"
+local function index(t, i)
+    if i == 1 and persistent and global[keys[1]] ~= nil then
+        return global[keys[1]]
+    end
+    return t[keys[i]]
+end
+local t = root or _ENV
+for i = 1, #keys - 1 do
+    local inner = index(t, i)
+    if type(inner) ~= "table" then
+        if op == "has" then
+            return false
+        end
+        error(string.format("'%s' is %s, expect a table", table.concat(keys, ".", 1, i), type(inner)), 0)
+    end
+    t = inner
+end
+local last = keys[#keys]
+if op == "get" then
+    return index(t, #keys)
+elseif op == "set" then
+    if #keys == 1 and persistent then
+        t = global
+    end
+    t[last] = value
+else
+    return index(t, #keys) ~= nil
+end
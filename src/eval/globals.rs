@@ -0,0 +1,87 @@
+//! typed access to lua globals by dotted paths, like `colors.red` or `settings.monitor.scale`.
+//!
+//! the path is passed to lua as a value, it is never formatted into a script.
+//!
+//! a script's `_ENV` may not outlive its eval, so the first key of a path is looked up
+//! in the host's persistent `global` table before `_ENV`, and [set] writes a single key
+//! into `global`. longer paths are set in whichever table the path leads to
+//! ```no_run
+//! # use cc_wasm_api::{eval::globals, lua_api::LuaResult};
+//! # async fn f() -> LuaResult<()> {
+//! let red: i32 = globals::get("colors.red").await?;
+//! globals::set("my_mod.state.ready", true).await?; // fails if `my_mod.state` is not a table
+//! let loaded = globals::has("my_mod").await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::lua_api::{
+    lua_ref::LuaRef, value::LuaValue, Exportable, Importable, LuaError, LuaResult,
+};
+
+use super::eval_with;
+
+/// lua source of the chunk which walks the path
+const ACCESS_SCRIPT: &str = include_str!("globals.lua");
+
+fn check_path(path: &str) -> LuaResult<()> {
+    if path.split('.').any(str::is_empty) {
+        Err(LuaError::from_string(format!(
+            "invalid global path \"{path}\""
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// the arguments of [ACCESS_SCRIPT], a missing `root` is passed as nil
+/// so the values after it stay in place
+fn access_args<'a, A>(
+    op: &'a str,
+    root: Option<&LuaRef>,
+    path: &'a str,
+    value: A,
+) -> (&'a str, LuaValue, &'a str, A) {
+    let root = root.cloned().map_or(LuaValue::Nil, LuaValue::Handle);
+    (op, root, path, value)
+}
+
+/// runs `op` on `path` of `root`, or of the globals if `root` is `None`
+pub(super) async fn access<O: Importable + Unpin + 'static, A: Exportable + Unpin>(
+    op: &str,
+    root: Option<&LuaRef>,
+    path: &str,
+    value: A,
+) -> LuaResult<O> {
+    check_path(path)?;
+    eval_with(ACCESS_SCRIPT, access_args(op, root, path, value)).await
+}
+
+/// the value at `path`, use [Option] if it may be nil.
+///
+/// fails if one of the tables on the path is missing
+pub async fn get<O: Importable + Unpin + 'static>(path: &str) -> LuaResult<O> {
+    access("get", None, path, ()).await
+}
+/// sets the value at `path`, [Nil](crate::lua_api::nil::Nil) or `()` removes it.
+///
+/// fails if one of the tables on the path is missing, they are not created
+pub async fn set<A: Exportable + Unpin>(path: &str, value: A) -> LuaResult<()> {
+    access("set", None, path, value).await
+}
+/// whether the value at `path` is not nil, `false` if one of the tables on the path is missing
+pub async fn has(path: &str) -> LuaResult<bool> {
+    access("has", None, path, ()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_without_root() {
+        assert_eq!(check_path("a.b"), Ok(()));
+        assert!(check_path("a..b").is_err());
+        let (op, root, path, value) = access_args("get", None, "a.b", ());
+        assert_eq!((op, root, path, value), ("get", LuaValue::Nil, "a.b", ()));
+    }
+}
@@ -0,0 +1,14 @@
+-- runs the script in `...` under xpcall, returns `true, results...` or `false, message, traceback`
+local src, name = ...
+local f, err = load(src, "=" .. name, "t", _ENV)
+if not f then
+    return false, tostring(err)
+end
+local function handler(e)
+    return { tostring(e), debug and debug.traceback and debug.traceback(nil, 2) }
+end
+local r = table.pack(xpcall(f, handler))
+if r[1] then
+    return table.unpack(r, 1, r.n)
+end
+return false, r[2][1], r[2][2]
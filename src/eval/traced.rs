@@ -0,0 +1,122 @@
+use std::{fmt::Display, future::Future};
+
+use crate::{
+    cc_mod::host::{has_capability, Capability},
+    lua_api::{Importable, LuaError, LuaResult},
+};
+
+use super::{eval, eval_with_import};
+
+/// lua source of the chunk which runs a script under `xpcall`
+const TRACED_SCRIPT: &str = include_str!("traced.lua");
+/// the chunk name of scripts run by [eval_traced]
+pub const DEFAULT_CHUNK_NAME: &str = "eval";
+
+/// an error raised by a script, with where it was raised
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvalError {
+    /// the error as lua reports it, usually starting with `chunk:line:`
+    pub message: String,
+    /// `debug.traceback` at the error, `None` for syntax errors
+    pub traceback: Option<String>,
+    /// the chunk name of the script
+    pub chunk: Option<String>,
+    /// the line of the script which failed, counting from 1.
+    ///
+    /// if the error was raised outside the script, this is the line of the script in the traceback
+    pub line: Option<usize>,
+}
+impl EvalError {
+    fn parse(chunk: Option<&str>, message: String, traceback: Option<String>) -> Self {
+        // `chunk:line: message`
+        let location = |s: &str| -> Option<(usize, usize)> {
+            let rest = s.strip_prefix(chunk?)?.strip_prefix(':')?;
+            let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+            let line = rest[..digits].parse().ok()?;
+            rest[digits..]
+                .starts_with(':')
+                .then(|| (line, s.len() - rest.len() + digits + 1))
+        };
+        let (line, message) = match location(&message) {
+            Some((line, end)) => (Some(line), message[end..].trim_start().to_owned()),
+            None => (None, message),
+        };
+        let line = line.or_else(|| {
+            traceback
+                .as_deref()?
+                .lines()
+                .find_map(|l| location(l.trim_start()))
+                .map(|(line, _)| line)
+        });
+        Self {
+            message,
+            traceback,
+            chunk: chunk.map(str::to_owned),
+            line,
+        }
+    }
+    /// the failing line of `source`, which must be the script that was run
+    pub fn source_line<'a>(&self, source: &'a str) -> Option<&'a str> {
+        source.lines().nth(self.line?.checked_sub(1)?)
+    }
+}
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(traceback) = &self.traceback {
+            write!(f, "\n{traceback}")?;
+        }
+        Ok(())
+    }
+}
+/// an error which did not come from the script, such as failing to import the results
+impl From<LuaError> for EvalError {
+    fn from(e: LuaError) -> Self {
+        Self {
+            message: e.as_str().to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+/// like [eval](super::eval), but the script runs under `xpcall` and errors carry a traceback.
+///
+/// on hosts without [EvalArgs](Capability::EvalArgs) the error only has the message
+/// ```no_run
+/// # use cc_wasm_api::{debug::show_str, eval::eval_traced};
+/// # async fn f() {
+/// let script = "local a = 1\nreturn a + nil";
+/// if let Err(e) = eval_traced::<i32>(script).await {
+///     show_str(&format!("{e}\nin: {}", e.source_line(script).unwrap_or("?")));
+/// }
+/// # }
+/// ```
+pub fn eval_traced<O: Importable + Unpin + 'static>(
+    script: &str,
+) -> impl '_ + Future<Output = Result<O, EvalError>> {
+    eval_traced_named(script, DEFAULT_CHUNK_NAME)
+}
+/// like [eval_traced], but returns nothing
+pub fn exec_traced(script: &str) -> impl '_ + Future<Output = Result<(), EvalError>> {
+    eval_traced(script)
+}
+/// like [eval_traced], `name` is the chunk name shown in the error and traceback
+pub async fn eval_traced_named<O: Importable + Unpin + 'static>(
+    script: &str,
+    name: &str,
+) -> Result<O, EvalError> {
+    if !has_capability(Capability::EvalArgs) {
+        return eval(script)
+            .await
+            .map_err(|e| EvalError::parse(None, e.as_str().to_owned(), None));
+    }
+    let import = || -> LuaResult<Result<O, EvalError>> {
+        if bool::import()? {
+            Ok(Ok(O::import()?))
+        } else {
+            let (message, traceback) = <(String, Option<String>)>::import()?;
+            Ok(Err(EvalError::parse(Some(name), message, traceback)))
+        }
+    };
+    eval_with_import(TRACED_SCRIPT, (script, name), import).await?
+}
@@ -13,6 +13,10 @@ pub mod addon {
 }
 
 pub mod prelude {
+    #[cfg(feature = "derive")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+    pub use crate::cc_export;
+    pub use crate::cc_mod::object::{LuaClass, Object};
     #[cfg(feature = "coroutine")]
     #[cfg_attr(docsrs, doc(cfg(feature = "coroutine")))]
     pub use crate::coroutine::{
@@ -21,17 +25,26 @@ pub mod prelude {
     };
     #[cfg(feature = "eval")]
     #[cfg_attr(docsrs, doc(cfg(feature = "eval")))]
-    pub use crate::eval::{eval, exec};
+    pub use crate::eval::{chunk::LuaChunk, env::LuaEnv, eval, eval_with, exec, exec_with};
     pub use crate::export_funcs;
-    pub use crate::lua_api::{nil::Nil, Exportable, Importable, LuaResult};
+    pub use crate::lua_api::{
+        function::LuaFunction, lua_ref::LuaRef, nil::Nil, value::LuaValue, Exportable, Importable,
+        LuaResult,
+    };
     pub use crate::utils::{either::Either, Number, SyncNonSync};
 }
 
 pub mod cc_mod;
+#[cfg(feature = "derive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+pub use cc_wasm_api_derive::cc_export;
 #[cfg(feature = "eval")]
 #[cfg_attr(docsrs, doc(cfg(feature = "eval")))]
 pub mod eval;
 pub mod lua_api;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod lua_serde;
 pub mod utils;
 pub mod debug {
     pub use dbg_inner::*;
@@ -75,14 +88,32 @@ pub mod coroutine;
 /// this function should not be manually called
 ///
 pub unsafe fn lib_exports() {
+    cc_mod::panic::install_hook();
+    "poisoned".export();
+    "abi_version".export();
+
     #[cfg(feature = "coroutine")]
     "tick".export();
     #[cfg(feature = "coroutine")]
     "stopped".export();
+    #[cfg(feature = "coroutine")]
+    "async_poll".export();
+    #[cfg(feature = "coroutine")]
+    "async_drop".export();
+    #[cfg(feature = "coroutine")]
+    "async_shim".export();
 
     #[cfg(feature = "eval")]
     "eval_result".export();
 
     #[cfg(feature = "eval")]
     "eval_string".export();
+
+    "object_call".export();
+    "object_drop".export();
+    "object_shim".export();
+
+    for registered in cc_mod::registry::registered() {
+        registered.info.name.export();
+    }
 }
@@ -26,18 +26,75 @@ mod lua_result {
 }
 /// types which can be exported to computer craft
 ///
-/// [i32], [i64], [f32], [f64], [String], ([Exportable], ...), [Option]<[Exportable]>, [[Exportable]], [Vec]<[Exportable]>
-/// and some other types impled [Exportable]
+/// integers, [f32], [f64], [char], [String], ([Exportable], ...), [Option]<[Exportable]>, [[Exportable]], [Vec]<[Exportable]>,
+/// [HashMap](std::collections::HashMap) and [BTreeMap](std::collections::BTreeMap) (as a lua table),
+/// [Box] and [Rc](std::rc::Rc) of them
+/// and some other types impled [Exportable].
+///
+/// `[u8]` and `Vec<u8>` are exported as a lua string
 pub trait Exportable {
     fn export(&self);
+    /// the lua type of the exported values, in EmmyLua syntax, used in generated type stubs.
+    ///
+    /// empty if nothing is exported
+    fn lua_export_type() -> String
+    where
+        Self: Sized,
+    {
+        "any".to_owned()
+    }
+    /// exports `[Self]`, as multiple values by default
+    #[doc(hidden)]
+    fn export_slice(slice: &[Self])
+    where
+        Self: Sized,
+    {
+        for i in slice {
+            i.export();
+        }
+    }
+    /// the lua type of `[Self]`
+    #[doc(hidden)]
+    fn lua_slice_type() -> String
+    where
+        Self: Sized,
+    {
+        format!("{} ...", Self::lua_export_type())
+    }
 }
 /// types which can be imported from computer craft's lua function call
 ///
-/// [i32], [i64], [f32], [f64], [String], ([Importable], ...), [Option]<[Importable]>, [Vec]<[Importable]>
-/// and some other types impled [Importable]
+/// integers, [f32], [f64], [char], [String], ([Importable], ...), [Option]<[Importable]>, [Vec]<[Importable]>,
+/// [HashMap](std::collections::HashMap) and [BTreeMap](std::collections::BTreeMap) (from a lua table),
+/// [Box] and [Rc](std::rc::Rc) of them
+/// and some other types impled [Importable].
+///
+/// integers are range checked, `Vec<u8>` is imported from a lua string.
+///
+/// `Vec<T>` is imported from a lua array if the next value is a table, otherwise from all the
+/// remaining values. so when `T` is itself imported from a table, like `Vec<Vec<i32>>` or
+/// `Vec<HashMap<K, V>>`, varargs `{1, 2}, {3, 4}` are read as the array `{1, 2}` and fail;
+/// pass such values wrapped in one table `{{1, 2}, {3, 4}}`
 pub trait Importable: Sized {
     fn import() -> LuaResult<Self>;
+    /// the lua type of the imported values, in EmmyLua syntax, used in generated type stubs
+    fn lua_import_type() -> String {
+        "any".to_owned()
+    }
+    /// imports `Vec<Self>`, from a lua array or all the remaining values by default
+    #[doc(hidden)]
+    fn import_vec() -> LuaResult<Vec<Self>> {
+        io_impl_utils::import_values()
+    }
+    /// the lua type of `Vec<Self>`
+    #[doc(hidden)]
+    fn lua_vec_type() -> String {
+        format!("{}[]", Self::lua_import_type())
+    }
 }
+#[cfg(feature = "derive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+pub use cc_wasm_api_derive::{Exportable, Importable};
 pub(crate) mod debug {
     use super::lua_ffi::{addrof, ffi};
     #[allow(unused)]
@@ -51,12 +108,15 @@ pub(crate) mod debug {
 pub use lua_ffi::abort_next_import;
 pub use lua_ffi::ffi::Typed;
 pub use lua_ffi::next_import_type;
+pub use lua_ffi::{export_table, import_table};
 pub use lua_ffi::{failed, success};
+pub use lua_ffi::{try_import, ImportSavepoint};
 pub(crate) mod lua_ffi {
 
     use ffi::Typed;
 
-    use super::{LuaError, LuaResult};
+    use super::{Importable, LuaError, LuaResult};
+    use crate::cc_mod::host::{has_capability, require, Capability};
 
     pub(crate) mod ffi {
         use std::fmt::Display;
@@ -77,6 +137,7 @@ pub(crate) mod lua_ffi {
             Object,
             Nil,
             Bool,
+            Handle,
             Error,
         }
         impl Typed {
@@ -92,6 +153,7 @@ pub(crate) mod lua_ffi {
                     7 => Typed::Object,
                     8 => Typed::Nil,
                     9 => Typed::Bool,
+                    10 => Typed::Handle,
                     _ => Typed::Error,
                 }
             }
@@ -109,6 +171,7 @@ pub(crate) mod lua_ffi {
                     Typed::Object => "table",
                     Typed::Nil => "nil",
                     Typed::Bool => "bool",
+                    Typed::Handle => "handle",
                     Typed::Error => "error type",
                 };
                 write!(f, "{}", msg)?;
@@ -151,6 +214,30 @@ pub(crate) mod lua_ffi {
 
             pub fn export_nil();
 
+            pub fn import_table_begin();
+            pub fn import_table_next() -> i32;
+            pub fn import_table_end();
+            pub fn export_table_begin();
+            pub fn export_table_end();
+
+            pub fn import_ref() -> i32;
+            pub fn export_handle(id: i32);
+            pub fn release_handle(id: i32);
+
+            pub fn import_savepoint() -> i32;
+            pub fn import_rollback(savepoint: i32);
+            pub fn import_release_savepoint(savepoint: i32);
+
+            pub fn import_number_count() -> i32;
+            pub fn import_i32_slice(addr: i32, len: i32) -> i32;
+            pub fn import_i64_slice(addr: i32, len: i32) -> i32;
+            pub fn import_f32_slice(addr: i32, len: i32) -> i32;
+            pub fn import_f64_slice(addr: i32, len: i32) -> i32;
+            pub fn export_i32_slice(addr: i32, len: i32);
+            pub fn export_i64_slice(addr: i32, len: i32);
+            pub fn export_f32_slice(addr: i32, len: i32);
+            pub fn export_f64_slice(addr: i32, len: i32);
+
             pub fn abort_next_import();
             pub fn success();
             pub fn failed();
@@ -224,6 +311,77 @@ pub(crate) mod lua_ffi {
                 todo!()
             }
 
+            pub unsafe fn import_table_begin() {
+                todo!()
+            }
+
+            pub unsafe fn import_table_next() -> i32 {
+                todo!()
+            }
+
+            pub unsafe fn import_table_end() {
+                todo!()
+            }
+
+            pub unsafe fn export_table_begin() {
+                todo!()
+            }
+
+            pub unsafe fn export_table_end() {
+                todo!()
+            }
+
+            pub unsafe fn import_ref() -> i32 {
+                todo!()
+            }
+
+            pub unsafe fn export_handle(id: i32) {
+                todo!()
+            }
+
+            pub unsafe fn release_handle(id: i32) {
+                todo!()
+            }
+
+            pub unsafe fn import_savepoint() -> i32 {
+                todo!()
+            }
+
+            pub unsafe fn import_rollback(savepoint: i32) {
+                todo!()
+            }
+            pub unsafe fn import_release_savepoint(savepoint: i32) {
+                todo!()
+            }
+
+            pub unsafe fn import_number_count() -> i32 {
+                todo!()
+            }
+            pub unsafe fn import_i32_slice(addr: i32, len: i32) -> i32 {
+                todo!()
+            }
+            pub unsafe fn import_i64_slice(addr: i32, len: i32) -> i32 {
+                todo!()
+            }
+            pub unsafe fn import_f32_slice(addr: i32, len: i32) -> i32 {
+                todo!()
+            }
+            pub unsafe fn import_f64_slice(addr: i32, len: i32) -> i32 {
+                todo!()
+            }
+            pub unsafe fn export_i32_slice(addr: i32, len: i32) {
+                todo!()
+            }
+            pub unsafe fn export_i64_slice(addr: i32, len: i32) {
+                todo!()
+            }
+            pub unsafe fn export_f32_slice(addr: i32, len: i32) {
+                todo!()
+            }
+            pub unsafe fn export_f64_slice(addr: i32, len: i32) {
+                todo!()
+            }
+
             pub unsafe fn abort_next_import() {
                 todo!()
             }
@@ -279,8 +437,86 @@ pub(crate) mod lua_ffi {
             Err(LuaError::from_string(format!("expect {}, got {}", t, next)))
         }
     }
+    /// a position in the values being imported, imports can be undone
+    /// by rolling back to it.
+    ///
+    /// the host releases the savepoint when it is rolled back or dropped
+    #[derive(Debug)]
+    pub struct ImportSavepoint(i32);
+    impl ImportSavepoint {
+        /// save the current import position, including inside tables
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Self {
+            Self(unsafe { ffi::import_savepoint() })
+        }
+        /// undo all the imports after this savepoint was created
+        pub fn rollback(self) {
+            unsafe {
+                ffi::import_rollback(self.0);
+            }
+            // the host releases it with the rollback
+            std::mem::forget(self);
+        }
+    }
+    impl Drop for ImportSavepoint {
+        fn drop(&mut self) {
+            unsafe {
+                ffi::import_release_savepoint(self.0);
+            }
+        }
+    }
+    /// import a value, if it fails, nothing is consumed.
+    ///
+    /// on hosts without [Savepoints](crate::cc_mod::host::Capability::Savepoints)
+    /// the values read by the failed import are consumed
+    pub fn try_import<T: Importable>() -> LuaResult<T> {
+        if !has_capability(Capability::Savepoints) {
+            return T::import();
+        }
+        let savepoint = ImportSavepoint::new();
+        let result = T::import();
+        if result.is_err() {
+            savepoint.rollback();
+        }
+        result
+    }
+    /// import a table, calling `each_pair` once for every key-value pair.
+    ///
+    /// inside `each_pair` the key and then the value are the next values to import.
+    /// the table is always closed, even if `each_pair` fails
+    pub fn import_table(mut each_pair: impl FnMut() -> LuaResult<()>) -> LuaResult<()> {
+        require(Capability::Tables)?;
+        assert_type(Typed::Object)?;
+        unsafe {
+            ffi::import_table_begin();
+        }
+        let result = (|| {
+            while unsafe { ffi::import_table_next() } != 0 {
+                each_pair()?;
+            }
+            Ok(())
+        })();
+        unsafe {
+            ffi::import_table_end();
+        }
+        result
+    }
+    /// export a table, every two values exported in `pairs` are a key and a value
+    pub fn export_table(pairs: impl FnOnce()) {
+        unsafe {
+            ffi::export_table_begin();
+        }
+        pairs();
+        unsafe {
+            ffi::export_table_end();
+        }
+    }
 }
 
+pub mod function;
+pub mod lua_ref;
+pub mod value;
+
 pub mod nil {
     use std::fmt::Display;
 
@@ -305,6 +541,9 @@ pub mod nil {
             <()>::import()?;
             Ok(Self)
         }
+        fn lua_import_type() -> String {
+            "nil".to_owned()
+        }
     }
     impl Exportable for Nil {
         fn export(&self) {
@@ -312,6 +551,9 @@ pub mod nil {
                 export_nil();
             }
         }
+        fn lua_export_type() -> String {
+            "nil".to_owned()
+        }
     }
 }
 
@@ -329,6 +571,9 @@ mod io_impl_string {
         fn export(&self) {
             export_string(self);
         }
+        fn lua_export_type() -> String {
+            "string".to_owned()
+        }
     }
     impl Exportable for str {
         fn export(&self) {
@@ -339,30 +584,32 @@ mod io_impl_string {
         fn export(&self) {
             export_string(self);
         }
+        fn lua_export_type() -> String {
+            "string".to_owned()
+        }
     }
     impl Exportable for &String {
         fn export(&self) {
             export_string(self);
         }
+        fn lua_export_type() -> String {
+            "string".to_owned()
+        }
     }
     impl Importable for String {
         fn import() -> LuaResult<Self> {
             import_string()
         }
-    }
-    impl Importable for Vec<u8> {
-        fn import() -> LuaResult<Self> {
-            import_bytes()
+        fn lua_import_type() -> String {
+            "string".to_owned()
         }
     }
-    impl Exportable for Vec<u8> {
-        fn export(&self) {
-            unsafe {
-                ffi::export_string(addrof(self), self.len() as i32);
-            }
+    fn export_string(s: &str) {
+        unsafe {
+            ffi::export_string(addrof(s), s.len() as i32);
         }
     }
-    fn export_string(s: &str) {
+    pub(super) fn export_bytes(s: &[u8]) {
         unsafe {
             ffi::export_string(addrof(s), s.len() as i32);
         }
@@ -380,7 +627,7 @@ mod io_impl_string {
         }
         String::from_utf8(a).map_err(|_| LuaError::from_str("non utf8 string"))
     }
-    fn import_bytes() -> LuaResult<Vec<u8>> {
+    pub(super) fn import_bytes() -> LuaResult<Vec<u8>> {
         // if next_import_type() != Typed::String {
         //     Err(LuaError::from_str("not receiving String"))?;
         // }
@@ -398,24 +645,47 @@ mod io_impl_string {
 mod io_impl_number {
     use super::{
         lua_ffi::ffi::{
-            export_f32, export_f64, export_i32, export_i64, import_f32, import_f64, import_i32,
-            import_i64, Typed,
+            export_f32, export_f32_slice, export_f64, export_f64_slice, export_i32,
+            export_i32_slice, export_i64, export_i64_slice, import_f32, import_f32_slice,
+            import_f64, import_f64_slice, import_i32_slice, import_i64_slice, import_number_count,
+            Typed,
         },
         Exportable, Importable,
     };
-    use crate::lua_api::lua_ffi::assert_type;
+    use crate::{
+        cc_mod::host::{has_capability, Capability},
+        lua_api::lua_ffi::assert_type,
+        utils::Number,
+    };
+
+    /// imports a lua array or the remaining values in one host call,
+    /// `None` if the host can not, then they are imported one by one
+    fn import_slice<T>(import: impl FnOnce(i32, i32) -> i32) -> Option<Vec<T>> {
+        if !has_capability(Capability::BulkNumbers) {
+            return None;
+        }
+        let len = usize::try_from(unsafe { import_number_count() }).ok()?;
+        let mut v = Vec::<T>::with_capacity(len);
+        // nothing is consumed if the host fails
+        if import(v.as_mut_ptr() as usize as i32, len as i32) == 0 {
+            return None;
+        }
+        unsafe { v.set_len(len) };
+        Some(v)
+    }
+
     macro_rules! impl_for {
-        ($t:ty, $typname:ident, $if:ident, $of:ident) => {
+        ($t:ty, $import:block, $of:ident, $lua_type:literal, $is:ident, $os:ident) => {
             impl Importable for $t {
-                fn import() -> super::LuaResult<Self> {
-                    // if next_import_type() != Typed::$typname {
-                    //     return Err(LuaError::from_str(concat!(
-                    //         "not receiving ",
-                    //         stringify!($t)
-                    //     )))?;
-                    // }
-                    assert_type(Typed::$typname)?;
-                    Ok(unsafe { $if() })
+                fn import() -> super::LuaResult<Self> $import
+                fn lua_import_type() -> String {
+                    $lua_type.to_owned()
+                }
+                fn import_vec() -> super::LuaResult<Vec<Self>> {
+                    match import_slice(|addr, len| unsafe { $is(addr, len) }) {
+                        Some(v) => Ok(v),
+                        None => super::io_impl_utils::import_values(),
+                    }
                 }
             }
             impl Exportable for $t {
@@ -424,21 +694,65 @@ mod io_impl_number {
                         $of(*self);
                     }
                 }
+                fn lua_export_type() -> String {
+                    $lua_type.to_owned()
+                }
+                /// exported from linear memory in one host call
+                fn export_slice(slice: &[Self]) {
+                    if !has_capability(Capability::BulkNumbers) {
+                        slice.iter().for_each(Exportable::export);
+                        return;
+                    }
+                    unsafe {
+                        $os(slice.as_ptr() as usize as i32, slice.len() as i32);
+                    }
+                }
             }
         };
     }
-    impl_for!(i32, I32, import_i32, export_i32);
-    impl_for!(i64, I64, import_i64, export_i64);
-    impl_for!(f32, F32, import_f32, export_f32);
-    impl_for!(f64, F64, import_f64, export_f64);
-}
-fn _a() {
-    concat!();
-    stringify!();
+    // range checked like the other integers, floats are accepted if they have no fractional part
+    impl_for!(
+        i32,
+        { Number::import()?.try_into() },
+        export_i32,
+        "integer",
+        import_i32_slice,
+        export_i32_slice
+    );
+    impl_for!(
+        i64,
+        { Number::import()?.try_into() },
+        export_i64,
+        "integer",
+        import_i64_slice,
+        export_i64_slice
+    );
+    impl_for!(
+        f32,
+        {
+            assert_type(Typed::F32)?;
+            Ok(unsafe { import_f32() })
+        },
+        export_f32,
+        "number",
+        import_f32_slice,
+        export_f32_slice
+    );
+    impl_for!(
+        f64,
+        {
+            assert_type(Typed::F64)?;
+            Ok(unsafe { import_f64() })
+        },
+        export_f64,
+        "number",
+        import_f64_slice,
+        export_f64_slice
+    );
 }
 mod io_impl_utils {
 
-    use super::{abort_next_import, next_import_type, Exportable, Importable, Typed};
+    use super::{abort_next_import, next_import_type, try_import, Exportable, Importable, Typed};
 
     impl Importable for () {
         fn import() -> super::LuaResult<Self> {
@@ -447,11 +761,18 @@ mod io_impl_utils {
             }
             Ok(())
         }
+        fn lua_import_type() -> String {
+            "nil".to_owned()
+        }
     }
     impl Exportable for () {
         fn export(&self) {}
+        fn lua_export_type() -> String {
+            String::new()
+        }
     }
 
+    /// if importing `T` fails, the values it read are not consumed
     impl<T: Importable> Importable for Option<T> {
         fn import() -> super::LuaResult<Self> {
             if next_import_type() == Typed::None {
@@ -460,9 +781,12 @@ mod io_impl_utils {
                 unsafe { abort_next_import() };
                 Ok(None)
             } else {
-                Ok(Some(Importable::import()?))
+                Ok(Some(try_import()?))
             }
         }
+        fn lua_import_type() -> String {
+            format!("{}?", T::lua_import_type())
+        }
     }
     impl<T: Exportable> Exportable for Option<T> {
         fn export(&self) {
@@ -470,6 +794,9 @@ mod io_impl_utils {
                 v.export()
             }
         }
+        fn lua_export_type() -> String {
+            format!("{}?", T::lua_export_type())
+        }
     }
 
     macro_rules! impl_tuple {
@@ -480,6 +807,9 @@ mod io_impl_utils {
                         $t::import()?,
                     )* ))
                 }
+                fn lua_import_type() -> String {
+                    [$($t::lua_import_type()),*].join(", ")
+                }
             }
 
             impl<$($t:Exportable),*> Exportable for ($($t,)*){
@@ -490,6 +820,13 @@ mod io_impl_utils {
                         $t.export();
                     )*
                 }
+                fn lua_export_type() -> String {
+                    [$($t::lua_export_type()),*]
+                        .into_iter()
+                        .filter(|t| !t.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
             }
         };
     }
@@ -508,17 +845,32 @@ mod io_impl_utils {
     impl_tuple!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10, T11, T12);
     impl_tuple!(T0, T1, T2, T3, T4, T5, T6, T7, T8, Y9, T10, T11, T12, T13);
 
+    /// if the next value is a table, it is imported as a lua array,
+    /// otherwise all the remaining values are imported.
+    ///
+    /// a leading table is always taken as the whole array, even if it is the first of several
+    /// tables passed as varargs
+    pub(super) fn import_values<T: Importable>() -> super::LuaResult<Vec<T>> {
+        if next_import_type() == Typed::Object {
+            return super::io_impl_table::import_array();
+        }
+        let mut v = Vec::new();
+        loop {
+            if next_import_type() != Typed::None {
+                v.push(T::import()?);
+            } else {
+                break;
+            }
+        }
+        Ok(v)
+    }
+    /// see [Importable::import_vec]
     impl<T: Importable> Importable for Vec<T> {
         fn import() -> super::LuaResult<Self> {
-            let mut v = Vec::new();
-            loop {
-                if next_import_type() != Typed::None {
-                    v.push(T::import()?);
-                } else {
-                    break;
-                }
-            }
-            Ok(v)
+            T::import_vec()
+        }
+        fn lua_import_type() -> String {
+            T::lua_vec_type()
         }
     }
     // impl<T: Importable + Default> Importable for [T; const { ("s", 1).1 + (1) }] {
@@ -534,6 +886,9 @@ mod io_impl_utils {
                         $t::import()?
                     ),*])
                 }
+                fn lua_import_type() -> String {
+                    [$($t::lua_import_type()),*].join(", ")
+                }
             }
         };
     }
@@ -579,37 +934,141 @@ mod io_impl_utils {
         T, T
     );
 
+    /// see [Exportable::export_slice]
     impl<T: Exportable> Exportable for [T] {
         fn export(&self) {
-            for i in self {
-                i.export();
-            }
+            T::export_slice(self);
         }
     }
     impl<T: Exportable> Exportable for &[T] {
         fn export(&self) {
             (*self).export();
         }
+        fn lua_export_type() -> String {
+            T::lua_slice_type()
+        }
     }
     impl<T: Exportable> Exportable for Vec<T> {
         fn export(&self) {
             self[..].export();
         }
+        fn lua_export_type() -> String {
+            T::lua_slice_type()
+        }
     }
     impl<T: Exportable> Exportable for &Vec<T> {
         fn export(&self) {
             self[..].export();
         }
+        fn lua_export_type() -> String {
+            T::lua_slice_type()
+        }
     }
     impl<T: Exportable, const L: usize> Exportable for [T; L] {
         fn export(&self) {
             self[..].export();
         }
+        fn lua_export_type() -> String {
+            T::lua_slice_type()
+        }
     }
     impl<T: Exportable, const L: usize> Exportable for &[T; L] {
         fn export(&self) {
             self[..].export();
         }
+        fn lua_export_type() -> String {
+            T::lua_slice_type()
+        }
+    }
+}
+
+mod io_impl_table {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        hash::{BuildHasher, Hash},
+    };
+
+    use super::{
+        lua_ffi::{export_table, import_table},
+        lua_result::LuaError,
+        Exportable, Importable, LuaResult,
+    };
+    use crate::utils::Number;
+
+    /// imports a table with keys `1..=n` as a [Vec], ordered by key
+    pub(super) fn import_array<T: Importable>() -> LuaResult<Vec<T>> {
+        let mut items = BTreeMap::new();
+        import_table(|| {
+            let key = match Number::import()? {
+                Number::Int(i) => i,
+                Number::Float(f) if f.fract() == 0. => f as i64,
+                Number::Float(_) => Err(LuaError::from_str("table is not an array"))?,
+            };
+            items.insert(key, T::import()?);
+            Ok(())
+        })?;
+        if items.keys().copied().ne(1..=items.len() as i64) {
+            Err(LuaError::from_str("table is not an array"))?;
+        }
+        Ok(items.into_values().collect())
+    }
+
+    impl<K: Importable + Eq + Hash, V: Importable, S: BuildHasher + Default> Importable
+        for HashMap<K, V, S>
+    {
+        fn import() -> LuaResult<Self> {
+            let mut map = HashMap::default();
+            import_table(|| {
+                let key = K::import()?;
+                map.insert(key, V::import()?);
+                Ok(())
+            })?;
+            Ok(map)
+        }
+        fn lua_import_type() -> String {
+            format!("table<{}, {}>", K::lua_import_type(), V::lua_import_type())
+        }
+    }
+    impl<K: Exportable, V: Exportable, S> Exportable for HashMap<K, V, S> {
+        fn export(&self) {
+            export_table(|| {
+                for (k, v) in self {
+                    k.export();
+                    v.export();
+                }
+            });
+        }
+        fn lua_export_type() -> String {
+            format!("table<{}, {}>", K::lua_export_type(), V::lua_export_type())
+        }
+    }
+
+    impl<K: Importable + Ord, V: Importable> Importable for BTreeMap<K, V> {
+        fn import() -> LuaResult<Self> {
+            let mut map = BTreeMap::new();
+            import_table(|| {
+                let key = K::import()?;
+                map.insert(key, V::import()?);
+                Ok(())
+            })?;
+            Ok(map)
+        }
+        fn lua_import_type() -> String {
+            format!("table<{}, {}>", K::lua_import_type(), V::lua_import_type())
+        }
+    }
+    impl<K: Exportable, V: Exportable> Exportable for BTreeMap<K, V> {
+        fn export(&self) {
+            export_table(|| {
+                for (k, v) in self {
+                    k.export();
+                    v.export();
+                }
+            });
+        }
+        fn lua_export_type() -> String {
+            format!("table<{}, {}>", K::lua_export_type(), V::lua_export_type())
+        }
     }
 }
 
@@ -627,6 +1086,9 @@ mod io_impl_bool {
             assert_type(Typed::Bool)?;
             Ok((unsafe { import_bool() }) != 0)
         }
+        fn lua_import_type() -> String {
+            "boolean".to_owned()
+        }
     }
     impl Exportable for bool {
         fn export(&self) {
@@ -634,5 +1096,177 @@ mod io_impl_bool {
                 export_bool(if *self { 1 } else { 0 });
             }
         }
+        fn lua_export_type() -> String {
+            "boolean".to_owned()
+        }
+    }
+}
+
+mod io_impl_integer {
+    use super::{
+        failed,
+        io_impl_string::{export_bytes, import_bytes},
+        lua_result::LuaError,
+        Exportable, Importable, LuaResult,
+    };
+    use crate::utils::Number;
+
+    /// range checked, floats are accepted if they have no fractional part
+    fn import_integer<T: TryFrom<Number, Error = LuaError>>() -> LuaResult<T> {
+        Number::import()?.try_into()
+    }
+
+    macro_rules! impl_for {
+        ($($t:ident),*) => {
+            $(
+                impl Importable for $t {
+                    fn import() -> LuaResult<Self> {
+                        import_integer()
+                    }
+                    fn lua_import_type() -> String {
+                        "integer".to_owned()
+                    }
+                }
+                /// integers which do not fit in an `i64` are exported as floats if that is exact,
+                /// otherwise the call fails with an error, the same as serde's `u64`
+                impl Exportable for $t {
+                    fn export(&self) {
+                        match i64::try_from(*self) {
+                            Ok(i) => i.export(),
+                            Err(_) if (*self as f64) as u128 == *self as u128 => {
+                                (*self as f64).export()
+                            }
+                            Err(_) => {
+                                unsafe { failed() };
+                                format!("{self} can not be exported exactly").export();
+                            }
+                        }
+                    }
+                    fn lua_export_type() -> String {
+                        "integer".to_owned()
+                    }
+                }
+            )*
+        };
+    }
+    impl_for!(i8, i16, u16, u32, u64, isize, usize);
+
+    impl Importable for u8 {
+        fn import() -> LuaResult<Self> {
+            import_integer()
+        }
+        fn lua_import_type() -> String {
+            "integer".to_owned()
+        }
+        /// `Vec<u8>` is imported from a lua string
+        fn import_vec() -> LuaResult<Vec<Self>> {
+            import_bytes()
+        }
+        fn lua_vec_type() -> String {
+            "string".to_owned()
+        }
+    }
+    impl Exportable for u8 {
+        fn export(&self) {
+            (*self as i32).export();
+        }
+        fn lua_export_type() -> String {
+            "integer".to_owned()
+        }
+        /// `[u8]` is exported as a lua string
+        fn export_slice(slice: &[Self]) {
+            export_bytes(slice);
+        }
+        fn lua_slice_type() -> String {
+            "string".to_owned()
+        }
+    }
+
+    /// a string of exactly one character
+    impl Importable for char {
+        fn import() -> LuaResult<Self> {
+            let s = String::import()?;
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(LuaError::from_string(format!(
+                    "expect a single character, got \"{s}\""
+                ))),
+            }
+        }
+        fn lua_import_type() -> String {
+            "string".to_owned()
+        }
+    }
+    impl Exportable for char {
+        fn export(&self) {
+            self.encode_utf8(&mut [0; 4]).export();
+        }
+        fn lua_export_type() -> String {
+            "string".to_owned()
+        }
+    }
+}
+
+mod io_impl_pointer {
+    use std::{borrow::Cow, rc::Rc};
+
+    use super::{Exportable, Importable, LuaResult};
+
+    macro_rules! impl_for {
+        ($($p:ident),*) => {
+            $(
+                impl<T: Importable> Importable for $p<T> {
+                    fn import() -> LuaResult<Self> {
+                        Ok($p::new(T::import()?))
+                    }
+                    fn lua_import_type() -> String {
+                        T::lua_import_type()
+                    }
+                }
+                impl<T: Exportable> Exportable for $p<T> {
+                    fn export(&self) {
+                        (**self).export();
+                    }
+                    fn lua_export_type() -> String {
+                        T::lua_export_type()
+                    }
+                }
+                impl Importable for $p<str> {
+                    fn import() -> LuaResult<Self> {
+                        Ok(String::import()?.into())
+                    }
+                    fn lua_import_type() -> String {
+                        "string".to_owned()
+                    }
+                }
+                impl Exportable for $p<str> {
+                    fn export(&self) {
+                        (**self).export();
+                    }
+                    fn lua_export_type() -> String {
+                        "string".to_owned()
+                    }
+                }
+            )*
+        };
+    }
+    impl_for!(Box, Rc);
+
+    impl Importable for Cow<'_, str> {
+        fn import() -> LuaResult<Self> {
+            Ok(Cow::Owned(String::import()?))
+        }
+        fn lua_import_type() -> String {
+            "string".to_owned()
+        }
+    }
+    impl Exportable for Cow<'_, str> {
+        fn export(&self) {
+            (**self).export();
+        }
+        fn lua_export_type() -> String {
+            "string".to_owned()
+        }
     }
 }
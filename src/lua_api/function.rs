@@ -0,0 +1,53 @@
+use super::{lua_ffi::assert_type, lua_ref::LuaRef, Exportable, Importable, LuaResult, Typed};
+
+/// a lua function received from lua, can be called later from a coroutine.
+///
+/// # Example
+/// ```no_run
+/// use cc_wasm_api::prelude::*;
+/// fn on_event(handler: LuaFunction) {
+///     async move {
+///         let accepted: bool = handler.call::<_, bool>(("redstone", 15)).await.unwrap();
+///     }
+///     .spawn();
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LuaFunction(LuaRef);
+
+impl LuaFunction {
+    pub fn as_lua_ref(&self) -> &LuaRef {
+        &self.0
+    }
+    pub fn into_lua_ref(self) -> LuaRef {
+        self.0
+    }
+}
+#[cfg(feature = "eval")]
+#[cfg_attr(docsrs, doc(cfg(feature = "eval")))]
+impl LuaFunction {
+    /// call the function with `args`, and import its return values as `O`
+    pub fn call<'a, A: Exportable + Unpin + 'a, O: Importable + Unpin + 'static>(
+        &'a self,
+        args: A,
+    ) -> impl 'a + std::future::Future<Output = LuaResult<O>> {
+        crate::eval::call_function(&self.0, args)
+    }
+}
+impl Importable for LuaFunction {
+    fn import() -> LuaResult<Self> {
+        assert_type(Typed::Handle)?;
+        Ok(Self(LuaRef::import()?))
+    }
+    fn lua_import_type() -> String {
+        "function".to_owned()
+    }
+}
+impl Exportable for LuaFunction {
+    fn export(&self) {
+        self.0.export();
+    }
+    fn lua_export_type() -> String {
+        "function".to_owned()
+    }
+}
@@ -0,0 +1,66 @@
+use std::rc::Rc;
+
+use crate::cc_mod::host::{require, Capability};
+
+use super::{lua_ffi::ffi, next_import_type, Exportable, Importable, LuaError, LuaResult, Typed};
+
+/// a reference to a lua value kept alive by the host's registry,
+/// such as a function, a peripheral or a table.
+///
+/// any lua value can be imported as a [LuaRef], it is exported as the same lua value.
+/// the value is released when the last clone of the [LuaRef] is dropped
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LuaRef(Rc<RefId>);
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct RefId(i32);
+impl Drop for RefId {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::release_handle(self.0);
+        }
+    }
+}
+
+impl LuaRef {
+    /// the id of the value in the host's registry
+    pub fn id(&self) -> i32 {
+        self.0 .0
+    }
+}
+#[cfg(feature = "eval")]
+#[cfg_attr(docsrs, doc(cfg(feature = "eval")))]
+impl LuaRef {
+    /// call `self.name(args)`, the way methods of a peripheral are called
+    pub fn call_method<'a, O: Importable + Unpin + 'static, A: Exportable + Unpin + 'a>(
+        &'a self,
+        name: &'a str,
+        args: A,
+    ) -> impl 'a + std::future::Future<Output = LuaResult<O>> {
+        crate::eval::call_method(self, name, false, args)
+    }
+    /// call `self:name(args)`, passing the value itself as the first argument
+    pub fn call_method_self<'a, O: Importable + Unpin + 'static, A: Exportable + Unpin + 'a>(
+        &'a self,
+        name: &'a str,
+        args: A,
+    ) -> impl 'a + std::future::Future<Output = LuaResult<O>> {
+        crate::eval::call_method(self, name, true, args)
+    }
+}
+impl Importable for LuaRef {
+    fn import() -> LuaResult<Self> {
+        if next_import_type() == Typed::None {
+            Err(LuaError::from_str("not receiving any value"))?;
+        }
+        require(Capability::Handles)?;
+        Ok(Self(Rc::new(RefId(unsafe { ffi::import_ref() }))))
+    }
+}
+impl Exportable for LuaRef {
+    fn export(&self) {
+        unsafe {
+            ffi::export_handle(self.id());
+        }
+    }
+}
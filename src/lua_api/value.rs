@@ -0,0 +1,259 @@
+use std::fmt::Display;
+
+use super::{
+    abort_next_import,
+    lua_ffi::{export_table, ffi::export_nil, import_table},
+    lua_ref::LuaRef,
+    next_import_type, Exportable, Importable, LuaError, LuaResult, Typed,
+};
+use crate::utils::Number;
+
+/// any lua value, can be inspected after being imported.
+///
+/// tables keep their key-value pairs in the order the host sends them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum LuaValue {
+    #[default]
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    /// lua strings are bytes, use [LuaValue::as_str] to read it as utf8
+    String(Vec<u8>),
+    Table(Vec<(LuaValue, LuaValue)>),
+    /// a value which can not be copied to rust, such as a function
+    Handle(LuaRef),
+}
+
+impl LuaValue {
+    /// the name of the type, same as lua's `type()`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            LuaValue::Nil => "nil",
+            LuaValue::Bool(_) => "boolean",
+            LuaValue::Integer(_) | LuaValue::Float(_) => "number",
+            LuaValue::String(_) => "string",
+            LuaValue::Table(_) => "table",
+            LuaValue::Handle(_) => "handle",
+        }
+    }
+    pub fn is_nil(&self) -> bool {
+        matches!(self, LuaValue::Nil)
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            LuaValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    /// returns the integer, or the float if it has no fractional part and fits in an [i64]
+    pub fn as_integer(&self) -> Option<i64> {
+        self.as_number()?.to_integer()
+    }
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            LuaValue::Integer(i) => Some(*i as f64),
+            LuaValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+    pub fn as_number(&self) -> Option<Number> {
+        match self {
+            LuaValue::Integer(i) => Some(Number::Int(*i)),
+            LuaValue::Float(f) => Some(Number::Float(*f)),
+            _ => None,
+        }
+    }
+    /// returns `None` if it is not a string or not utf8
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.as_bytes()?).ok()
+    }
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            LuaValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_table(&self) -> Option<&[(LuaValue, LuaValue)]> {
+        match self {
+            LuaValue::Table(t) => Some(t),
+            _ => None,
+        }
+    }
+    pub fn as_handle(&self) -> Option<&LuaRef> {
+        match self {
+            LuaValue::Handle(h) => Some(h),
+            _ => None,
+        }
+    }
+    /// index a table, returns `None` if it is not a table or the key is not found
+    pub fn get(&self, key: impl Into<LuaValue>) -> Option<&LuaValue> {
+        let key = key.into();
+        self.as_table()?
+            .iter()
+            .find(|(k, _)| k.raw_eq(&key))
+            .map(|(_, v)| v)
+    }
+    /// the values of `t[1]`, `t[2]`, ... until the first missing index
+    pub fn array(&self) -> impl '_ + Iterator<Item = &LuaValue> {
+        (1..).map_while(|i: i64| self.get(i))
+    }
+    /// compares like lua's `rawequal`, so `1 == 1.0`
+    fn raw_eq(&self, other: &LuaValue) -> bool {
+        match (self, other) {
+            (LuaValue::Integer(a), LuaValue::Float(b))
+            | (LuaValue::Float(b), LuaValue::Integer(a)) => *a as f64 == *b,
+            _ => self == other,
+        }
+    }
+}
+
+impl Importable for LuaValue {
+    fn import() -> LuaResult<Self> {
+        Ok(match next_import_type() {
+            Typed::None => Err(LuaError::from_str("not receiving any value"))?,
+            Typed::Nil => {
+                unsafe { abort_next_import() };
+                LuaValue::Nil
+            }
+            Typed::Bool => LuaValue::Bool(bool::import()?),
+            Typed::I32 => LuaValue::Integer(i32::import()? as i64),
+            Typed::I64 => LuaValue::Integer(i64::import()?),
+            Typed::F32 => LuaValue::Float(f32::import()? as f64),
+            Typed::F64 => LuaValue::Float(f64::import()?),
+            Typed::String => LuaValue::String(Vec::<u8>::import()?),
+            Typed::Object => {
+                let mut pairs = Vec::new();
+                import_table(|| {
+                    let key = LuaValue::import()?;
+                    pairs.push((key, LuaValue::import()?));
+                    Ok(())
+                })?;
+                LuaValue::Table(pairs)
+            }
+            Typed::Handle => LuaValue::Handle(LuaRef::import()?),
+            Typed::Type => Err(LuaError::from_str("receiving type"))?,
+            Typed::Error => Err(LuaError::from_str("receiving error type"))?,
+        })
+    }
+}
+impl Exportable for LuaValue {
+    fn export(&self) {
+        match self {
+            LuaValue::Nil => unsafe { export_nil() },
+            LuaValue::Bool(b) => b.export(),
+            LuaValue::Integer(i) => i.export(),
+            LuaValue::Float(f) => f.export(),
+            LuaValue::String(s) => s.export(),
+            LuaValue::Table(t) => export_table(|| {
+                for (k, v) in t {
+                    k.export();
+                    v.export();
+                }
+            }),
+            LuaValue::Handle(h) => h.export(),
+        }
+    }
+}
+impl Display for LuaValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LuaValue::Nil => write!(f, "nil"),
+            LuaValue::Bool(b) => b.fmt(f),
+            LuaValue::Integer(i) => i.fmt(f),
+            LuaValue::Float(n) => n.fmt(f),
+            LuaValue::String(s) => String::from_utf8_lossy(s).fmt(f),
+            LuaValue::Table(t) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in t.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "[{k}] = {v}")?;
+                }
+                write!(f, "}}")
+            }
+            LuaValue::Handle(h) => write!(f, "handle: {}", h.id()),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($t:ty, $v:ident, $e:expr) => {
+        impl From<$t> for LuaValue {
+            fn from($v: $t) -> Self {
+                $e
+            }
+        }
+    };
+}
+impl_from!(bool, v, LuaValue::Bool(v));
+impl_from!(i32, v, LuaValue::Integer(v as i64));
+impl_from!(i64, v, LuaValue::Integer(v));
+impl_from!(f32, v, LuaValue::Float(v as f64));
+impl_from!(f64, v, LuaValue::Float(v));
+impl_from!(&str, v, LuaValue::String(v.as_bytes().to_vec()));
+impl_from!(String, v, LuaValue::String(v.into_bytes()));
+impl_from!(Vec<u8>, v, LuaValue::String(v));
+impl_from!(LuaRef, v, LuaValue::Handle(v));
+impl_from!(super::nil::Nil, _v, LuaValue::Nil);
+impl_from!(
+    Number,
+    v,
+    match v {
+        Number::Int(i) => LuaValue::Integer(i),
+        Number::Float(f) => LuaValue::Float(f),
+    }
+);
+impl<T: Into<LuaValue>> From<Option<T>> for LuaValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(LuaValue::Nil, Into::into)
+    }
+}
+
+macro_rules! impl_try_from {
+    ($t:ty, $name:literal, $v:ident, $e:expr) => {
+        impl TryFrom<LuaValue> for $t {
+            type Error = LuaError;
+
+            fn try_from($v: LuaValue) -> Result<Self, Self::Error> {
+                let type_name = $v.type_name();
+                $e.ok_or_else(|| {
+                    LuaError::from_string(format!("expect {}, got {}", $name, type_name))
+                })
+            }
+        }
+    };
+}
+impl_try_from!(bool, "boolean", v, v.as_bool());
+impl_try_from!(i64, "integer", v, v.as_integer());
+impl_try_from!(f64, "number", v, v.as_float());
+impl_try_from!(Number, "number", v, v.as_number());
+impl_try_from!(String, "string", v, v.as_str().map(str::to_owned));
+impl_try_from!(
+    Vec<u8>,
+    "string",
+    v,
+    match v {
+        LuaValue::String(s) => Some(s),
+        _ => None,
+    }
+);
+impl_try_from!(
+    Vec<(LuaValue, LuaValue)>,
+    "table",
+    v,
+    match v {
+        LuaValue::Table(t) => Some(t),
+        _ => None,
+    }
+);
+impl_try_from!(
+    LuaRef,
+    "handle",
+    v,
+    match v {
+        LuaValue::Handle(h) => Some(h),
+        _ => None,
+    }
+);
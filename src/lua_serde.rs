@@ -0,0 +1,118 @@
+//! convert any [serde] type from and to lua values.
+//!
+//! values are converted through [LuaValue], so a value is only exported
+//! when it is fully serialized.
+//!
+//! | rust | lua |
+//! | --- | --- |
+//! | `bool`, numbers, strings, bytes | the same type |
+//! | `None`, `()`, unit struct | `nil` |
+//! | sequences, tuples | array table, with `n = len` like `table.pack` if it has nil |
+//! | maps, structs | table |
+//! | unit variant | string, the variant name |
+//! | other variants | `{ [variant name] = value }` |
+
+mod de;
+mod ser;
+
+use std::fmt::Display;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::lua_api::{failed, value::LuaValue, Exportable, Importable, LuaResult};
+
+/// error while converting between a serde type and a [LuaValue]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for Error {}
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+impl serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// serialize a value to a [LuaValue]
+pub fn to_lua_value<T: Serialize + ?Sized>(value: &T) -> Result<LuaValue, Error> {
+    value.serialize(ser::ValueSerializer)
+}
+/// deserialize a value from a [LuaValue]
+pub fn from_lua_value<T: DeserializeOwned>(value: LuaValue) -> Result<T, Error> {
+    T::deserialize(value)
+}
+/// serialize a value and export it
+pub fn export_serialize<T: Serialize + ?Sized>(value: &T) -> LuaResult<()> {
+    to_lua_value(value)?.export();
+    Ok(())
+}
+/// import the next value and deserialize it
+pub fn import_deserialize<T: DeserializeOwned>() -> LuaResult<T> {
+    Ok(from_lua_value(LuaValue::import()?)?)
+}
+
+/// a wrapper which impls [Importable] and [Exportable] with [serde].
+///
+/// if the value can not be serialized, nothing of it is exported and the current call fails
+/// with the error, use [export_serialize] or [to_lua_value] to handle the error
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Serde<T>(pub T);
+impl<T: DeserializeOwned> Importable for Serde<T> {
+    fn import() -> LuaResult<Self> {
+        import_deserialize().map(Self)
+    }
+}
+impl<T: Serialize> Exportable for Serde<T> {
+    fn export(&self) {
+        match to_lua_value(&self.0) {
+            Ok(v) => v.export(),
+            Err(e) => {
+                unsafe { failed() };
+                format!("failed to serialize: {e}").export();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn u64_round_trip() {
+        let big = 1u64 << 63;
+        assert_eq!(to_lua_value(&big), Ok(LuaValue::Float(big as f64)));
+        assert_eq!(from_lua_value::<u64>(to_lua_value(&big).unwrap()), Ok(big));
+        assert!(to_lua_value(&u64::MAX).is_err());
+        assert!(from_lua_value::<u64>(LuaValue::Float(1e300)).is_err());
+        assert!(from_lua_value::<i64>(LuaValue::Float(big as f64)).is_err());
+    }
+
+    #[test]
+    fn sequence_with_nil() {
+        let v = vec![Some(1), None, Some(3)];
+        let lua = to_lua_value(&v).unwrap();
+        assert_eq!(lua.get("n"), Some(&LuaValue::Integer(3)));
+        assert_eq!(lua.get(2), None);
+        assert_eq!(from_lua_value::<Vec<Option<i32>>>(lua), Ok(v));
+
+        let v: Vec<Option<i32>> = vec![None, None];
+        assert_eq!(
+            from_lua_value::<Vec<Option<i32>>>(to_lua_value(&v).unwrap()),
+            Ok(v)
+        );
+        let v = (1, None::<i32>);
+        assert_eq!(
+            from_lua_value::<(i32, Option<i32>)>(to_lua_value(&v).unwrap()),
+            Ok(v)
+        );
+    }
+}
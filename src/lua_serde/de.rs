@@ -0,0 +1,258 @@
+use serde::{
+    de::{
+        value::{MapDeserializer, SeqDeserializer},
+        EnumAccess, Error as _, IntoDeserializer, Unexpected, VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserializer,
+};
+
+use super::{ser::U64_END, Error};
+use crate::lua_api::value::LuaValue;
+
+/// the values of a table with keys `1..=n`, ordered by key.
+///
+/// if the table has an `n` key, like the result of `table.pack`, it is the length and
+/// the missing values are nil
+fn array_values(pairs: &[(LuaValue, LuaValue)]) -> Option<Vec<LuaValue>> {
+    let n = pairs.iter().find(|(k, _)| k.as_str() == Some("n"));
+    let len = match n {
+        Some((_, n)) => usize::try_from(n.as_integer()?).ok()?,
+        None => pairs.len(),
+    };
+    let mut items: Vec<Option<LuaValue>> = Vec::new();
+    items.try_reserve_exact(len).ok()?;
+    items.resize(len, None);
+    for (k, v) in pairs {
+        if k.as_str() == Some("n") {
+            continue;
+        }
+        let index = k.as_integer()?;
+        let slot = items.get_mut(usize::try_from(index).ok()?.checked_sub(1)?)?;
+        *slot = Some(v.clone());
+    }
+    match n {
+        Some(_) => Some(items.into_iter().map(Option::unwrap_or_default).collect()),
+        None => items.into_iter().collect(),
+    }
+}
+
+impl LuaValue {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            LuaValue::Nil => Unexpected::Unit,
+            LuaValue::Bool(b) => Unexpected::Bool(*b),
+            LuaValue::Integer(i) => Unexpected::Signed(*i),
+            LuaValue::Float(f) => Unexpected::Float(*f),
+            LuaValue::String(s) => Unexpected::Bytes(s),
+            LuaValue::Table(_) => Unexpected::Map,
+            LuaValue::Handle(_) => Unexpected::Other("handle"),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for LuaValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($f:ident),*) => {
+        $(
+            // floats without fractional part are accepted, lua does not always keep the subtype
+            fn $f<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.as_integer() {
+                    Some(i) => visitor.visit_i64(i),
+                    None => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for LuaValue {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            LuaValue::Nil => visitor.visit_unit(),
+            LuaValue::Bool(b) => visitor.visit_bool(b),
+            LuaValue::Integer(i) => visitor.visit_i64(i),
+            LuaValue::Float(f) => visitor.visit_f64(f),
+            LuaValue::String(s) => match String::from_utf8(s) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            LuaValue::Table(pairs) => match array_values(&pairs) {
+                Some(items) if !items.is_empty() => {
+                    visitor.visit_seq(SeqDeserializer::new(items.into_iter()))
+                }
+                _ => visitor.visit_map(MapDeserializer::new(pairs.into_iter())),
+            },
+            LuaValue::Handle(_) => Err(Error::custom("can not deserialize a handle")),
+        }
+    }
+
+    deserialize_integer!(
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32
+    );
+
+    /// also accepts floats in `[2^63, 2^64)`, which is how [u64]s larger than [i64::MAX] are serialized
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            LuaValue::Float(f) if f.fract() == 0. && (0. ..U64_END).contains(&f) => {
+                visitor.visit_u64(f as u64)
+            }
+            v => match v.as_integer() {
+                Some(i) => visitor.visit_i64(i),
+                None => v.deserialize_any(visitor),
+            },
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            LuaValue::Nil => visitor.visit_none(),
+            v => visitor.visit_some(v),
+        }
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let items = match &self {
+            LuaValue::Table(pairs) => array_values(pairs),
+            _ => None,
+        };
+        match items {
+            Some(items) => visitor.visit_seq(SeqDeserializer::new(items.into_iter())),
+            None => Err(Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            LuaValue::Table(pairs) => visitor.visit_map(MapDeserializer::new(pairs.into_iter())),
+            v => Err(Error::invalid_type(v.unexpected(), &visitor)),
+        }
+    }
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            LuaValue::String(_) => visitor.visit_enum(Enum(self, None)),
+            LuaValue::Table(mut pairs) if pairs.len() == 1 => {
+                let (variant, value) = pairs.pop().unwrap();
+                visitor.visit_enum(Enum(variant, Some(value)))
+            }
+            v => Err(Error::invalid_type(
+                v.unexpected(),
+                &"string or single-key table",
+            )),
+        }
+    }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            LuaValue::Nil => visitor.visit_unit(),
+            v => Err(Error::invalid_type(v.unexpected(), &visitor)),
+        }
+    }
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf identifier
+    }
+}
+
+/// variant name and the value, `None` for unit variants
+struct Enum(LuaValue, Option<LuaValue>);
+impl<'de> EnumAccess<'de> for Enum {
+    type Error = Error;
+    type Variant = Variant;
+
+    fn variant_seed<V: serde::de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Variant), Error> {
+        Ok((seed.deserialize(self.0)?, Variant(self.1)))
+    }
+}
+struct Variant(Option<LuaValue>);
+impl Variant {
+    fn value(self) -> Result<LuaValue, Error> {
+        self.0
+            .ok_or_else(|| Error::invalid_type(Unexpected::UnitVariant, &"variant with a value"))
+    }
+}
+impl<'de> VariantAccess<'de> for Variant {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.0 {
+            None | Some(LuaValue::Nil) => Ok(()),
+            Some(v) => Err(Error::invalid_type(v.unexpected(), &"unit variant")),
+        }
+    }
+    fn newtype_variant_seed<T: serde::de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Error> {
+        seed.deserialize(self.value()?)
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.value()?.deserialize_seq(visitor)
+    }
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.value()?.deserialize_map(visitor)
+    }
+}
@@ -0,0 +1,285 @@
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize, Serializer,
+};
+
+use super::Error;
+use crate::lua_api::value::LuaValue;
+
+/// `2^64`
+pub(super) const U64_END: f64 = 18_446_744_073_709_551_616.;
+
+pub(super) struct ValueSerializer;
+
+/// `{ [variant] = value }`
+fn variant(variant: &'static str, value: LuaValue) -> LuaValue {
+    LuaValue::Table(vec![(variant.into(), value)])
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = LuaValue;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<LuaValue, Error> {
+        Ok(LuaValue::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<LuaValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i16(self, v: i16) -> Result<LuaValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i32(self, v: i32) -> Result<LuaValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i64(self, v: i64) -> Result<LuaValue, Error> {
+        Ok(LuaValue::Integer(v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<LuaValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_u16(self, v: u16) -> Result<LuaValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_u32(self, v: u32) -> Result<LuaValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_u64(self, v: u64) -> Result<LuaValue, Error> {
+        // lua integers are i64, larger values can only be floats, if they are exact
+        if let Ok(i) = i64::try_from(v) {
+            return Ok(LuaValue::Integer(i));
+        }
+        let f = v as f64;
+        if f < U64_END && f as u64 == v {
+            Ok(LuaValue::Float(f))
+        } else {
+            Err(Error(format!("{v} can not be represented as a lua number")))
+        }
+    }
+    fn serialize_f32(self, v: f32) -> Result<LuaValue, Error> {
+        self.serialize_f64(v.into())
+    }
+    fn serialize_f64(self, v: f64) -> Result<LuaValue, Error> {
+        Ok(LuaValue::Float(v))
+    }
+    fn serialize_char(self, v: char) -> Result<LuaValue, Error> {
+        Ok(LuaValue::String(v.to_string().into_bytes()))
+    }
+    fn serialize_str(self, v: &str) -> Result<LuaValue, Error> {
+        Ok(v.into())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<LuaValue, Error> {
+        Ok(LuaValue::String(v.to_vec()))
+    }
+    fn serialize_none(self) -> Result<LuaValue, Error> {
+        Ok(LuaValue::Nil)
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<LuaValue, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<LuaValue, Error> {
+        Ok(LuaValue::Nil)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<LuaValue, Error> {
+        Ok(LuaValue::Nil)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<LuaValue, Error> {
+        Ok(variant.into())
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<LuaValue, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant_name: &'static str,
+        value: &T,
+    ) -> Result<LuaValue, Error> {
+        Ok(variant(variant_name, value.serialize(self)?))
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer {
+            pairs: Vec::with_capacity(len.unwrap_or(0)),
+            next_key: None,
+            variant: None,
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer, Error> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer {
+            pairs: Vec::with_capacity(len),
+            next_key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+pub(super) struct SeqSerializer {
+    items: Vec<(LuaValue, LuaValue)>,
+    variant: Option<&'static str>,
+}
+impl SeqSerializer {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let index = self.items.len() as i64 + 1;
+        self.items
+            .push((index.into(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+    fn finish(mut self) -> Result<LuaValue, Error> {
+        // lua tables can not hold nil, `n` keeps the length like `table.pack`
+        let len = self.items.len() as i64;
+        if self.items.iter().any(|(_, v)| v.is_nil()) {
+            self.items.retain(|(_, v)| !v.is_nil());
+            self.items.push(("n".into(), len.into()));
+        }
+        let table = LuaValue::Table(self.items);
+        Ok(match self.variant {
+            Some(name) => variant(name, table),
+            None => table,
+        })
+    }
+}
+macro_rules! impl_seq {
+    ($tr:ident, $f:ident) => {
+        impl $tr for SeqSerializer {
+            type Ok = LuaValue;
+            type Error = Error;
+
+            fn $f<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+                self.push(value)
+            }
+            fn end(self) -> Result<LuaValue, Error> {
+                self.finish()
+            }
+        }
+    };
+}
+impl_seq!(SerializeSeq, serialize_element);
+impl_seq!(SerializeTuple, serialize_element);
+impl_seq!(SerializeTupleStruct, serialize_field);
+impl_seq!(SerializeTupleVariant, serialize_field);
+
+pub(super) struct MapSerializer {
+    pairs: Vec<(LuaValue, LuaValue)>,
+    next_key: Option<LuaValue>,
+    variant: Option<&'static str>,
+}
+impl MapSerializer {
+    fn insert(&mut self, key: LuaValue, value: LuaValue) {
+        // lua tables can not hold nil
+        if !value.is_nil() {
+            self.pairs.push((key, value));
+        }
+    }
+    fn finish(self) -> Result<LuaValue, Error> {
+        let table = LuaValue::Table(self.pairs);
+        Ok(match self.variant {
+            Some(name) => variant(name, table),
+            None => table,
+        })
+    }
+}
+impl SerializeMap for MapSerializer {
+    type Ok = LuaValue;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        let key = key.serialize(ValueSerializer)?;
+        if key.is_nil() {
+            Err(Error("table key can not be nil".to_owned()))?;
+        }
+        self.next_key = Some(key);
+        Ok(())
+    }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| Error("serialize_value called before serialize_key".to_owned()))?;
+        let value = value.serialize(ValueSerializer)?;
+        self.insert(key, value);
+        Ok(())
+    }
+    fn end(self) -> Result<LuaValue, Error> {
+        self.finish()
+    }
+}
+macro_rules! impl_struct {
+    ($tr:ident) => {
+        impl $tr for MapSerializer {
+            type Ok = LuaValue;
+            type Error = Error;
+
+            fn serialize_field<T: ?Sized + Serialize>(
+                &mut self,
+                key: &'static str,
+                value: &T,
+            ) -> Result<(), Error> {
+                let value = value.serialize(ValueSerializer)?;
+                self.insert(key.into(), value);
+                Ok(())
+            }
+            fn end(self) -> Result<LuaValue, Error> {
+                self.finish()
+            }
+        }
+    };
+}
+impl_struct!(SerializeStruct);
+impl_struct!(SerializeStructVariant);
@@ -7,6 +7,8 @@ use crate::lua_api::LuaResult;
 pub struct ImplResult;
 pub struct ImplIter;
 pub struct ImplValue;
+pub struct ImplAsyncResult;
+pub struct ImplAsyncValue;
 
 pub trait Debuged<T> {
     fn debuged(self) -> LuaResult<T>;
@@ -42,11 +44,16 @@ mod other_type {
 
     use std::fmt::Display;
 
-    use crate::lua_api::{next_import_type, Exportable, Importable, LuaError, LuaResult, Typed};
+    use crate::lua_api::{
+        lua_ffi::ffi::{import_i32, import_i64},
+        next_import_type, Exportable, Importable, LuaError, LuaResult, Typed,
+    };
 
     /// a Number type, can be both float or int.
-    /// impls [Importable] and [Exportable]
-    #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+    /// impls [Importable] and [Exportable].
+    ///
+    /// the operators and comparisons follow lua's rules: integers wrap around, `1 == 1.0`
+    #[derive(Debug, Copy, Clone)]
     pub enum Number {
         Int(i64),
         Float(f64),
@@ -90,13 +97,17 @@ mod other_type {
                 Number::Float(f) => f.export(),
             }
         }
+        fn lua_export_type() -> String {
+            "number".to_owned()
+        }
     }
     impl Importable for Number {
         fn import() -> LuaResult<Self> {
             match next_import_type() {
                 Typed::None => Err(LuaError::from_str("not receiving any value")),
-                Typed::I32 => Ok(Self::Int(i32::import()? as i64)),
-                Typed::I64 => Ok(Self::Int(i64::import()?)),
+                // not through `i32::import`, which imports a `Number` itself
+                Typed::I32 => Ok(Self::Int(unsafe { import_i32() } as i64)),
+                Typed::I64 => Ok(Self::Int(unsafe { import_i64() })),
                 Typed::String => Err(LuaError::from_str("receiving string")),
                 Typed::F32 => Ok(Self::Float(f32::import()? as f64)),
                 Typed::F64 => Ok(Self::Float(f64::import()?)),
@@ -105,8 +116,12 @@ mod other_type {
                 Typed::Error => Err(LuaError::from_str("receiving error type")),
                 Typed::Nil => Err(LuaError::from_str("receiving nil")),
                 Typed::Bool => Err(LuaError::from_str("receiving bool")),
+                Typed::Handle => Err(LuaError::from_str("receiving handle")),
             }
         }
+        fn lua_import_type() -> String {
+            "number".to_owned()
+        }
     }
     impl Display for Number {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -118,9 +133,216 @@ mod other_type {
     }
 }
 
+/// lua 5.4 semantics of arithmetic and comparisons on [Number]
+mod number_ops {
+    use std::{
+        cmp::Ordering,
+        ops::{Add, Div, Mul, Neg, Rem, Sub},
+    };
+
+    use super::Number;
+    use crate::lua_api::LuaError;
+
+    /// `2^63`, the first float above `i64::MAX`
+    const I64_END: f64 = 9_223_372_036_854_775_808.;
+
+    impl Number {
+        /// like lua's `math.tointeger`, floats are converted only if they have no fractional part
+        pub fn to_integer(self) -> Option<i64> {
+            match self {
+                Number::Int(i) => Some(i),
+                Number::Float(f) if f.fract() == 0. && (-I64_END..I64_END).contains(&f) => {
+                    Some(f as i64)
+                }
+                Number::Float(_) => None,
+            }
+        }
+        /// like [Number::to_i32], but `None` on overflow or fractional input
+        pub fn checked_i32(self) -> Option<i32> {
+            self.try_into().ok()
+        }
+        /// like [Number::to_i64], but `None` on overflow or fractional input
+        pub fn checked_i64(self) -> Option<i64> {
+            self.to_integer()
+        }
+        pub fn is_int(self) -> bool {
+            matches!(self, Number::Int(_))
+        }
+        /// `//`, integers are floored and wrap around, floats are `floor(a / b)`
+        /// # Panics
+        /// if both are integers and `rhs` is 0, like lua's `attempt to perform 'n//0'`
+        pub fn floor_div(self, rhs: Number) -> Number {
+            self.checked_floor_div(rhs)
+                .expect("attempt to perform 'n//0'")
+        }
+        /// `//`, `None` if both are integers and `rhs` is 0
+        pub fn checked_floor_div(self, rhs: Number) -> Option<Number> {
+            Some(match (self, rhs) {
+                (Number::Int(_), Number::Int(0)) => None?,
+                (Number::Int(m), Number::Int(-1)) => Number::Int(m.wrapping_neg()),
+                (Number::Int(m), Number::Int(n)) => {
+                    let q = m / n;
+                    // rounds towards minus infinity
+                    if (m ^ n) < 0 && m % n != 0 {
+                        Number::Int(q - 1)
+                    } else {
+                        Number::Int(q)
+                    }
+                }
+                (a, b) => Number::Float((a.to_f64() / b.to_f64()).floor()),
+            })
+        }
+        /// `%`, `None` if both are integers and `rhs` is 0
+        pub fn checked_rem(self, rhs: Number) -> Option<Number> {
+            Some(match (self, rhs) {
+                (Number::Int(_), Number::Int(0)) => None?,
+                (Number::Int(_), Number::Int(-1)) => Number::Int(0),
+                (Number::Int(m), Number::Int(n)) => {
+                    let r = m % n;
+                    // the result has the sign of the divisor
+                    if r != 0 && (r ^ n) < 0 {
+                        Number::Int(r + n)
+                    } else {
+                        Number::Int(r)
+                    }
+                }
+                (a, b) => {
+                    let (a, b) = (a.to_f64(), b.to_f64());
+                    let m = a % b;
+                    if (m > 0. && b < 0.) || (m < 0. && b > 0.) {
+                        Number::Float(m + b)
+                    } else {
+                        Number::Float(m)
+                    }
+                }
+            })
+        }
+    }
+
+    macro_rules! impl_arith {
+        ($tr:ident, $f:ident, $int:ident, $op:tt) => {
+            /// integers wrap around, mixed operands are converted to floats
+            impl $tr for Number {
+                type Output = Number;
+                fn $f(self, rhs: Number) -> Number {
+                    match (self, rhs) {
+                        (Number::Int(a), Number::Int(b)) => Number::Int(a.$int(b)),
+                        (a, b) => Number::Float(a.to_f64() $op b.to_f64()),
+                    }
+                }
+            }
+        };
+    }
+    impl_arith!(Add, add, wrapping_add, +);
+    impl_arith!(Sub, sub, wrapping_sub, -);
+    impl_arith!(Mul, mul, wrapping_mul, *);
+
+    /// `/` is always a float division
+    impl Div for Number {
+        type Output = Number;
+        fn div(self, rhs: Number) -> Number {
+            Number::Float(self.to_f64() / rhs.to_f64())
+        }
+    }
+    /// # Panics
+    /// if both are integers and `rhs` is 0, like lua's `attempt to perform 'n%0'`
+    impl Rem for Number {
+        type Output = Number;
+        fn rem(self, rhs: Number) -> Number {
+            self.checked_rem(rhs).expect("attempt to perform 'n%0'")
+        }
+    }
+    impl Neg for Number {
+        type Output = Number;
+        fn neg(self) -> Number {
+            match self {
+                Number::Int(i) => Number::Int(i.wrapping_neg()),
+                Number::Float(f) => Number::Float(-f),
+            }
+        }
+    }
+
+    /// compares an integer with a float exactly, without rounding the integer
+    fn cmp_int_float(i: i64, f: f64) -> Option<Ordering> {
+        if f.is_nan() {
+            None
+        } else if f >= I64_END {
+            Some(Ordering::Less)
+        } else if f < -I64_END {
+            Some(Ordering::Greater)
+        } else {
+            let t = f.trunc();
+            match i.cmp(&(t as i64)) {
+                Ordering::Equal => 0f64.partial_cmp(&(f - t)),
+                ord => Some(ord),
+            }
+        }
+    }
+    /// `1 == 1.0`, like lua
+    impl PartialEq for Number {
+        fn eq(&self, other: &Number) -> bool {
+            self.partial_cmp(other) == Some(Ordering::Equal)
+        }
+    }
+    impl PartialOrd for Number {
+        fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+            match (*self, *other) {
+                (Number::Int(a), Number::Int(b)) => Some(a.cmp(&b)),
+                (Number::Float(a), Number::Float(b)) => a.partial_cmp(&b),
+                (Number::Int(a), Number::Float(b)) => cmp_int_float(a, b),
+                (Number::Float(a), Number::Int(b)) => cmp_int_float(b, a).map(Ordering::reverse),
+            }
+        }
+    }
+
+    macro_rules! impl_from {
+        ($($t:ty => $v:ident),*) => {
+            $(
+                impl From<$t> for Number {
+                    fn from(value: $t) -> Self {
+                        Number::$v(value.into())
+                    }
+                }
+            )*
+        };
+    }
+    impl_from!(i32 => Int, i64 => Int, f32 => Float, f64 => Float);
+
+    macro_rules! impl_try_into {
+        ($($t:ident),*) => {
+            $(
+                /// range checked, floats are accepted if they have no fractional part
+                impl TryFrom<Number> for $t {
+                    type Error = LuaError;
+                    fn try_from(value: Number) -> Result<Self, LuaError> {
+                        let i = match value {
+                            Number::Float(f) if f.fract() != 0. || f.is_nan() => {
+                                Err(LuaError::from_str("number has no integer representation"))?
+                            }
+                            n => n.to_integer().ok_or_else(|| {
+                                LuaError::from_string(format!(
+                                    "{n} is out of range for {}",
+                                    stringify!($t)
+                                ))
+                            })?,
+                        };
+                        $t::try_from(i).map_err(|_| {
+                            LuaError::from_string(format!(
+                                "{i} is out of range for {}",
+                                stringify!($t)
+                            ))
+                        })
+                    }
+                }
+            )*
+        };
+    }
+    impl_try_into!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+}
+
 pub mod either {
     use crate::{
-        lua_api::LuaError,
+        lua_api::{try_import, LuaError},
         prelude::{Exportable, Importable},
     };
 
@@ -136,16 +358,98 @@ pub mod either {
                 Either::Second(r) => r.export(),
             }
         }
+        fn lua_export_type() -> String {
+            format!("{}|{}", L::lua_export_type(), R::lua_export_type())
+        }
     }
+    /// tries `L` first, then `R`. the values read by a failed attempt are not consumed
     impl<L: Importable, R: Importable> Importable for Either<L, R> {
         fn import() -> crate::prelude::LuaResult<Self> {
-            if let Ok(v) = L::import() {
-                Ok(Self::First(v))
-            } else if let Ok(v) = R::import() {
-                Ok(Self::Second(v))
-            } else {
-                Err(LuaError::from_str("neither of the value matches"))
+            let first_err = match try_import::<L>() {
+                Ok(v) => return Ok(Self::First(v)),
+                Err(e) => e,
+            };
+            match try_import::<R>() {
+                Ok(v) => Ok(Self::Second(v)),
+                Err(second_err) => Err(LuaError::from_string(format!(
+                    "neither of the value matches ({}; {})",
+                    first_err.as_str(),
+                    second_err.as_str()
+                ))),
             }
         }
+        fn lua_import_type() -> String {
+            format!("{}|{}", L::lua_import_type(), R::lua_import_type())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::Number::{self, Float, Int};
+
+    /// `==` treats `1` and `1.0` as equal, this also checks the variant
+    fn assert_same(a: Number, b: Number) {
+        assert_eq!(a.is_int(), b.is_int(), "{a} and {b}");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn floor_div_and_rem() {
+        assert_same(Int(7).floor_div(Int(-2)), Int(-4));
+        assert_same(Int(-7).floor_div(Int(2)), Int(-4));
+        assert_same(Int(-7) % Int(2), Int(1));
+        assert_same(Int(7) % Int(-2), Int(-1));
+        assert_same(Float(5.5) % Float(-2.), Float(-0.5));
+        assert_same(Float(-5.5) % Int(2), Float(0.5));
+        assert_same(Float(7.).floor_div(Int(-2)), Float(-4.));
+        assert_eq!(Int(1).checked_floor_div(Int(0)), None);
+        assert_eq!(Int(1).checked_rem(Int(0)), None);
+        assert!(matches!(Int(1).floor_div(Float(0.)), Float(f) if f == f64::INFINITY));
+    }
+
+    #[test]
+    fn integer_overflow_wraps() {
+        assert_same(Int(i64::MIN).floor_div(Int(-1)), Int(i64::MIN));
+        assert_same(Int(i64::MIN) % Int(-1), Int(0));
+        assert_same(Int(i64::MAX) + Int(1), Int(i64::MIN));
+        assert_same(Int(i64::MIN) - Int(1), Int(i64::MAX));
+        assert_same(-Int(i64::MIN), Int(i64::MIN));
+        assert_same(Int(1) + Float(0.5), Float(1.5));
+        assert_same(Int(1) / Int(2), Float(0.5));
+    }
+
+    #[test]
+    fn int_float_comparison() {
+        assert_eq!(Int(1), Float(1.));
+        assert!(Int(1) < Float(1.5));
+        assert!(Float(-0.5) < Int(0));
+        // 2^53 + 1 is not representable as a float, it must not be rounded
+        assert!(Int((1 << 53) + 1) > Float((1u64 << 53) as f64));
+        assert!(Int(i64::MAX) < Float(9_223_372_036_854_775_808.));
+        assert!(Int(i64::MIN) == Float(-9_223_372_036_854_775_808.));
+        assert_eq!(Int(0).partial_cmp(&Float(f64::NAN)), None);
+        assert_ne!(Float(f64::NAN), Float(f64::NAN));
+        assert_eq!(
+            Float(f64::NEG_INFINITY).partial_cmp(&Int(i64::MIN)),
+            Some(Ordering::Less)
+        );
+    }
+
+    #[test]
+    fn checked_conversions() {
+        assert_eq!(u8::try_from(Int(255)), Ok(255));
+        assert!(u8::try_from(Int(256)).is_err());
+        assert!(u8::try_from(Int(-1)).is_err());
+        assert_eq!(i32::try_from(Float(-3.)), Ok(-3));
+        assert!(i32::try_from(Float(0.5)).is_err());
+        assert!(i32::try_from(Float(f64::NAN)).is_err());
+        assert!(i64::try_from(Float(9_223_372_036_854_775_808.)).is_err());
+        assert_eq!(u64::try_from(Int(i64::MAX)), Ok(i64::MAX as u64));
+        assert_eq!(Float(2.).checked_i32(), Some(2));
+        assert_eq!(Int(1 << 40).checked_i32(), None);
+        assert_eq!(Float(1e300).checked_i64(), None);
     }
 }
@@ -0,0 +1,86 @@
+use cc_wasm_api::{
+    cc_mod::{object::LuaClass, registry},
+    prelude::*,
+};
+
+#[derive(Importable, Exportable)]
+#[lua(rename_all = "camelCase")]
+struct Config {
+    max_speed: f64,
+    label: Option<String>,
+}
+
+#[derive(Importable, Exportable)]
+enum Mode {
+    Fast,
+    Slow,
+}
+
+#[cc_export(name = "setSpeed", args(side, speed = 1.0))]
+fn set_speed(side: String, speed: f64) -> LuaResult<()> {
+    let _ = (side, speed);
+    Ok(())
+}
+
+#[cc_export]
+fn get_config() -> Config {
+    Config {
+        max_speed: 1.0,
+        label: None,
+    }
+}
+
+struct Counter(i32);
+#[cc_export]
+impl Counter {
+    pub fn add(&mut self, n: i32) -> i32 {
+        self.0 += n;
+        self.0
+    }
+    pub fn get(&self) -> i32 {
+        self.0
+    }
+}
+
+#[test]
+fn lua_types() {
+    assert_eq!(
+        Config::lua_import_type(),
+        "{maxSpeed: number, label: string?}"
+    );
+    assert_eq!(
+        Config::lua_export_type(),
+        "{maxSpeed: number, label: string?}"
+    );
+    assert_eq!(Mode::lua_import_type(), "\"Fast\"|\"Slow\"");
+    assert_eq!(Mode::lua_export_type(), "\"Fast\"|\"Slow\"");
+}
+
+/// the constructors run again before every exported function on wasm32-wasip1
+#[test]
+fn exports_are_registered_once() {
+    let entries: Vec<_> = registry::registered().collect();
+    entries.into_iter().for_each(registry::submit);
+    let mut names: Vec<_> = registry::registered().map(|r| r.info.name).collect();
+    names.sort();
+    assert_eq!(names, ["get_config", "setSpeed"]);
+}
+
+#[test]
+fn describe_exports() {
+    let mut stubs: Vec<_> = registry::describe_registered().collect();
+    stubs.sort();
+    assert_eq!(
+        stubs,
+        [
+            "---@param side string\n---@param speed number?\nfunction M.setSpeed(side, speed) end\n",
+            "---@return {maxSpeed: number, label: string?}\nfunction M.get_config() end\n",
+        ]
+    );
+}
+
+#[test]
+fn class() {
+    assert_eq!(Counter::class_name(), "Counter");
+    assert_eq!(Counter::methods(), ["add", "get"]);
+}